@@ -0,0 +1,298 @@
+use std::any::type_name;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::debug;
+
+const MAGIC: &[u8; 4] = b"AOC1";
+
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Compact binary encoding for parsed inputs. Integers are little-endian,
+/// `usize` is stored as a `u64` and strings and vectors are length-prefixed.
+pub trait Cacheable: Sized {
+    /// Part of the cache key. Bump it whenever the encoding or the meaning of
+    /// the fields changes, so that old entries aren't decoded into the wrong
+    /// fields.
+    const VERSION: u32 = 0;
+
+    fn encode(&self, buf: &mut Vec<u8>);
+    fn decode(r: &mut Decoder) -> Option<Self>;
+}
+
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
+
+macro_rules! impl_cacheable_int {
+    ($($t:ty),*) => {$(
+        impl Cacheable for $t {
+            fn encode(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode(r: &mut Decoder) -> Option<Self> {
+                let bytes = r.take(std::mem::size_of::<$t>())?;
+                Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+impl_cacheable_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Cacheable for usize {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as u64).encode(buf);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        u64::decode(r)?.try_into().ok()
+    }
+}
+
+impl Cacheable for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        match u8::decode(r)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Cacheable for String {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        let len = usize::decode(r)?;
+        String::from_utf8(r.take(len)?.to_vec()).ok()
+    }
+}
+
+impl<T: Cacheable> Cacheable for Vec<T> {
+    const VERSION: u32 = T::VERSION;
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for e in self.iter() {
+            e.encode(buf);
+        }
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        let len = usize::decode(r)?;
+        // Don't trust the length for preallocation, the file may be corrupt.
+        let mut v = Vec::new();
+        for _ in 0..len {
+            v.push(T::decode(r)?);
+        }
+        Some(v)
+    }
+}
+
+impl<A: Cacheable, B: Cacheable> Cacheable for (A, B) {
+    const VERSION: u32 = A::VERSION.wrapping_mul(31).wrapping_add(B::VERSION);
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.0.encode(buf);
+        self.1.encode(buf);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        Some((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+impl<A: Cacheable, B: Cacheable, C: Cacheable> Cacheable for (A, B, C) {
+    const VERSION: u32 = <(A, B)>::VERSION.wrapping_mul(31).wrapping_add(C::VERSION);
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.0.encode(buf);
+        self.1.encode(buf);
+        self.2.encode(buf);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        Some((A::decode(r)?, B::decode(r)?, C::decode(r)?))
+    }
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` it is stable between Rust releases,
+/// so cache keys stay valid.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes.iter() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Identifies the parsed type and the version of its encoding.
+fn type_hash<T: Cacheable>() -> u64 {
+    let mut key = type_name::<T>().as_bytes().to_vec();
+    key.extend_from_slice(&T::VERSION.to_le_bytes());
+    fnv1a(&key)
+}
+
+/// Turns on caching of parsed inputs in `dir`.
+pub fn enable(dir: impl Into<PathBuf>) {
+    *CACHE_DIR.write().unwrap() = Some(dir.into());
+}
+
+pub fn disable() {
+    *CACHE_DIR.write().unwrap() = None;
+}
+
+/// Parses `file_path` with `parse`, or loads the result of an earlier parse of
+/// the same input from the cache if caching is enabled. Entries are keyed by
+/// the hash of the input file and the parsed type with its version.
+pub fn cached<T: Cacheable>(file_path: &str, parse: impl FnOnce(&str) -> T) -> T {
+    let dir = match CACHE_DIR.read().unwrap().clone() {
+        Some(dir) => dir,
+        None => return parse(file_path),
+    };
+
+    let input = match fs::read(file_path) {
+        Ok(bytes) => bytes,
+        Err(_) => return parse(file_path),
+    };
+    let type_hash = type_hash::<T>();
+    let path = dir.join(format!("{:016x}-{:016x}.bin", fnv1a(&input), type_hash));
+
+    if let Some(value) = load(&path, type_hash) {
+        debug!("Loaded {} from cache", file_path);
+        return value;
+    }
+
+    let value = parse(file_path);
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    type_hash.encode(&mut buf);
+    value.encode(&mut buf);
+
+    // A failed write only costs us the next parse.
+    if fs::create_dir_all(&dir).and_then(|_| fs::write(&path, buf)).is_err() {
+        debug!("Could not write cache entry {}", path.display());
+    }
+    value
+}
+
+fn load<T: Cacheable>(path: &Path, type_hash: u64) -> Option<T> {
+    let bytes = fs::read(path).ok()?;
+    let mut r = Decoder::new(&bytes);
+    if r.take(MAGIC.len())? != MAGIC || u64::decode(&mut r)? != type_hash {
+        return None;
+    }
+    let value = T::decode(&mut r)?;
+    if r.is_empty() { Some(value) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn roundtrip<T: Cacheable>(value: &T) -> Option<T> {
+        let mut buf = Vec::new();
+        value.encode(&mut buf);
+        T::decode(&mut Decoder::new(&buf))
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value = (
+            vec![(1i64, -2i64), (i64::MAX, i64::MIN)],
+            vec!["r".to_string(), "wr".to_string(), "".to_string()],
+            (usize::MAX, true),
+        );
+        assert_eq!(Some(value.clone()), roundtrip(&value));
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let mut buf = Vec::new();
+        vec![1u32, 2, 3].encode(&mut buf);
+        buf.pop();
+        assert_eq!(None, Vec::<u32>::decode(&mut Decoder::new(&buf)));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Point(u32, u32);
+
+    impl Cacheable for Point {
+        const VERSION: u32 = 2;
+
+        fn encode(&self, buf: &mut Vec<u8>) {
+            (self.0, self.1).encode(buf);
+        }
+
+        fn decode(r: &mut Decoder) -> Option<Self> {
+            let (x, y) = <(u32, u32)>::decode(r)?;
+            Some(Point(x, y))
+        }
+    }
+
+    #[test]
+    fn test_type_hash() {
+        // An entry from before the version bump must not match.
+        let mut old_key = type_name::<Point>().as_bytes().to_vec();
+        old_key.extend_from_slice(&1u32.to_le_bytes());
+        assert_ne!(fnv1a(&old_key), type_hash::<Point>());
+
+        // Versions of nested types end up in the key too.
+        assert_eq!(2, <Vec<(u8, Point)>>::VERSION);
+        assert_ne!(<(Point, u8)>::VERSION, <(u8, Point)>::VERSION);
+    }
+
+    #[test]
+    fn test_cached() {
+        let dir = env::temp_dir().join(format!("aoc-cache-{}", std::process::id()));
+        let input = dir.join("input.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&input, "1,2,3").unwrap();
+        let input = input.to_str().unwrap();
+
+        let parse = |path: &str| -> Vec<u32> {
+            fs::read_to_string(path).unwrap().split(',').map(|e| e.parse().unwrap()).collect()
+        };
+
+        enable(dir.join("cache"));
+        assert_eq!(vec![1, 2, 3], cached(input, parse));
+        assert_eq!(vec![1, 2, 3], cached(input, |_| -> Vec<u32> { panic!("not cached") }));
+
+        // A changed input gets a new entry.
+        fs::write(input, "4,5").unwrap();
+        assert_eq!(vec![4, 5], cached(input, parse));
+        disable();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
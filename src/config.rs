@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{env, error, fmt, fs, io};
+
+use crate::input::InputManager;
+use crate::log::Level;
+
+const CONFIG_FILE: &str = "aoc.toml";
+const ENV_PREFIX: &str = "AOC_";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax { line: usize, msg: String },
+    Value { key: String, value: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Syntax { line, msg } => write!(f, "line {}: {}", line, msg),
+            ConfigError::Value { key, value } => {
+                write!(f, "invalid value '{}' for {}", value, key)
+            },
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("unknown output format '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InputConfig {
+    pub dir: PathBuf,
+    pub layout: String,
+    pub session: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    pub iterations: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub dir: PathBuf,
+}
+
+/// Settings shared by all the solution binaries, read from `aoc.toml`.
+///
+/// Every `key` in a `[section]` can be overridden by an environment variable
+/// named `AOC_<SECTION>_<KEY>`, e.g. `AOC_LOG_LEVEL=debug` or
+/// `AOC_DAY14_WIDTH=11`. `AOC_SESSION` is accepted for `input.session` and
+/// `AOC_CONFIG` points to a different config file.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub input: InputConfig,
+    pub output: OutputFormat,
+    pub bench: BenchConfig,
+    pub cache: CacheConfig,
+    pub log_level: Level,
+    params: HashMap<String, HashMap<String, String>>,
+}
+
+type Tables = HashMap<String, HashMap<String, String>>;
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = env::var("AOC_CONFIG").unwrap_or(CONFIG_FILE.to_string());
+        let contents = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut tables = parse_tables(&contents)?;
+        apply_env_overrides(&mut tables, env::vars());
+        Self::from_tables(tables)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        Self::from_tables(parse_tables(contents)?)
+    }
+
+    fn from_tables(mut tables: Tables) -> Result<Self, ConfigError> {
+        let input = InputConfig {
+            dir: PathBuf::from(take(&mut tables, "input", "dir").unwrap_or("src".to_string())),
+            layout: take(&mut tables, "input", "layout")
+                .unwrap_or("day{day}/input.txt".to_string()),
+            session: take(&mut tables, "input", "session"),
+        };
+        let output = take_parsed(&mut tables, "output", "format")?
+            .unwrap_or(OutputFormat::Text);
+        let bench = BenchConfig {
+            iterations: take_parsed(&mut tables, "bench", "iterations")?.unwrap_or(1),
+        };
+        let cache = CacheConfig {
+            enabled: take_parsed(&mut tables, "cache", "enabled")?.unwrap_or(false),
+            dir: PathBuf::from(
+                take(&mut tables, "cache", "dir").unwrap_or("target/aoc-cache".to_string())
+            ),
+        };
+        let log_level = take_parsed(&mut tables, "log", "level")?.unwrap_or(Level::Info);
+
+        Ok(Self { input, output, bench, cache, log_level, params: tables })
+    }
+
+    pub fn input_manager(&self) -> InputManager {
+        let mut manager = InputManager::new(&self.input.dir).layout(&self.input.layout);
+        if let Some(token) = &self.input.session {
+            manager = manager.session(token.as_str());
+        }
+        manager
+    }
+
+    /// A puzzle parameter from the `[dayNN]` section, e.g. the room size in
+    /// day 14.
+    pub fn param<T: FromStr>(&self, day: usize, key: &str, default: T) -> T {
+        let section = format!("day{:02}", day);
+        match self.params.get(&section).and_then(|t| t.get(key)) {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                panic!("{}", ConfigError::Value {
+                    key: format!("{}.{}", section, key),
+                    value: value.clone(),
+                })
+            }),
+            None => default,
+        }
+    }
+}
+
+fn take(tables: &mut Tables, section: &str, key: &str) -> Option<String> {
+    tables.get_mut(section).and_then(|t| t.remove(key))
+}
+
+fn take_parsed<T: FromStr>(
+    tables: &mut Tables,
+    section: &str,
+    key: &str
+) -> Result<Option<T>, ConfigError> {
+    match take(tables, section, key) {
+        Some(value) => match value.parse() {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(ConfigError::Value { key: format!("{}.{}", section, key), value }),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Parses the small part of TOML we need: `[section]` headers followed by
+/// `key = value` lines, where values are quoted strings, numbers or booleans.
+fn parse_tables(contents: &str) -> Result<Tables, ConfigError> {
+    let mut tables: Tables = HashMap::new();
+    let mut section = String::new();
+
+    for (i, raw_line) in contents.lines().enumerate() {
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() { continue; }
+
+        let syntax_error = |msg: &str| ConfigError::Syntax { line: i + 1, msg: msg.to_string() };
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name.strip_suffix(']').ok_or_else(|| syntax_error("unclosed section"))?;
+            section = name.trim().to_string();
+            continue;
+        }
+
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| syntax_error("expected 'key = value'"))?;
+        let key = key.trim();
+        let value = value.trim();
+        if key.is_empty() || value.is_empty() {
+            return Err(syntax_error("expected 'key = value'"));
+        }
+
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            quoted.strip_suffix('"')
+                .ok_or_else(|| syntax_error("unterminated string"))?
+                .replace("\\\"", "\"")
+        } else {
+            value.to_string()
+        };
+        tables.entry(section.clone()).or_default().insert(key.to_string(), value);
+    }
+    Ok(tables)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {},
+        }
+    }
+    line
+}
+
+fn apply_env_overrides(tables: &mut Tables, vars: impl Iterator<Item = (String, String)>) {
+    for (name, value) in vars {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else { continue; };
+        let (section, key) = match rest {
+            "CONFIG" => continue,
+            "SESSION" => ("input", "session"),
+            _ => match rest.split_once('_') {
+                Some(pair) => pair,
+                None => continue,
+            },
+        };
+        tables.entry(section.to_ascii_lowercase()).or_default()
+            .insert(key.to_ascii_lowercase(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(r#"
+            # Comment
+            [input]
+            dir = "inputs" # trailing comment
+            layout = "{day}.txt"
+
+            [output]
+            format = "json"
+
+            [bench]
+            iterations = 25
+
+            [cache]
+            enabled = true
+
+            [day14]
+            width = 11
+        "#).unwrap();
+
+        assert_eq!(PathBuf::from("inputs"), config.input.dir);
+        assert_eq!("{day}.txt", config.input.layout);
+        assert_eq!(OutputFormat::Json, config.output);
+        assert_eq!(25, config.bench.iterations);
+        assert!(config.cache.enabled);
+        assert_eq!(PathBuf::from("target/aoc-cache"), config.cache.dir);
+        assert_eq!(Level::Info, config.log_level);
+        assert_eq!(11, config.param(14, "width", 101));
+        assert_eq!(103, config.param(14, "height", 103));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut tables = parse_tables("[log]\nlevel = \"info\"\n[day18]\nsize = 70").unwrap();
+        let vars = [
+            ("AOC_LOG_LEVEL", "debug"),
+            ("AOC_DAY18_SIZE", "6"),
+            ("AOC_SESSION", "abc"),
+            ("HOME", "/root"),
+        ];
+        apply_env_overrides(
+            &mut tables,
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+        let config = Config::from_tables(tables).unwrap();
+
+        assert_eq!(Level::Debug, config.log_level);
+        assert_eq!(6, config.param(18, "size", 70));
+        assert_eq!(Some("abc".to_string()), config.input.session);
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert!(matches!(
+            Config::parse("[bench]\niterations = many"),
+            Err(ConfigError::Value { .. })
+        ));
+        assert!(matches!(
+            Config::parse("[output\nformat = \"text\""),
+            Err(ConfigError::Syntax { line: 1, .. })
+        ));
+    }
+}
@@ -1,63 +1,530 @@
-use std::fs::File;
-use std::io::Read;
-use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::env;
+use advent_of_code_2024::config::OutputFormat;
+use advent_of_code_2024::runner::{json_string, Runner};
 
-fn parse_input(file_path: &str) -> (Vec<i32>, Vec<i32>) { 
+fn parse_lists(file_path: &str) -> Vec<Vec<i32>> {
     let mut input = String::new();
     let mut f = File::open(file_path).unwrap();
     f.read_to_string(&mut input).unwrap();
 
-    let mut v1 = Vec::new();
-    let mut v2 = Vec::new();
+    let mut lists: Vec<Vec<i32>> = Vec::new();
 
     for l in input.lines() {
         let ids: Vec<&str> = l.split_whitespace().collect();
-        v1.push(i32::from_str_radix(ids[0], 10).unwrap());
-        v2.push(i32::from_str_radix(ids[1], 10).unwrap());
+        if lists.is_empty() {
+            lists = vec![Vec::new(); ids.len()];
+        }
+        assert_eq!(ids.len(), lists.len(), "Every line needs one ID per list");
+
+        for (list, id) in lists.iter_mut().zip(ids.iter()) {
+            list.push(i32::from_str_radix(id, 10).unwrap());
+        }
     }
-    (v1, v2)
+    lists
 }
 
-fn part1(file_path: &str) -> i32 {
-    let (mut group_one_ids, mut group_two_ids) = parse_input(file_path);
+fn parse_input(file_path: &str) -> (Vec<i32>, Vec<i32>) { 
+    let mut lists = parse_lists(file_path).into_iter();
+    (lists.next().unwrap_or_default(), lists.next().unwrap_or_default())
+}
+
+trait Metric {
+    fn name(&self) -> &str;
+    /// `None` where the metric isn't defined for the lists.
+    fn compare(&self, a: &[i32], b: &[i32]) -> Option<f64>;
+}
+
+/// Differences between the lists when the smallest ID is paired with the
+/// smallest ID and so on, as in part 1.
+fn sorted_differences(a: &[i32], b: &[i32]) -> Vec<f64> {
+    assert_eq!(a.len(), b.len());
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs() as f64).collect()
+}
+
+/// Sum of the differences between the lists paired up as in part 1.
+fn total_distance(a: &[i32], b: &[i32]) -> i32 {
+    assert_eq!(a.len(), b.len());
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).sum()
+}
+
+struct L1;
+
+impl Metric for L1 {
+    fn name(&self) -> &str { "L1" }
 
-    group_one_ids.sort();
-    group_two_ids.sort();
+    fn compare(&self, a: &[i32], b: &[i32]) -> Option<f64> {
+        Some(total_distance(a, b) as f64)
+    }
+}
+
+struct L2;
 
-    let mut total_distance = 0;
+impl Metric for L2 {
+    fn name(&self) -> &str { "L2" }
 
-    for i in 0..group_one_ids.len() {
-        let difference = (group_one_ids[i] - group_two_ids[i]).abs();
-        total_distance += difference;
+    fn compare(&self, a: &[i32], b: &[i32]) -> Option<f64> {
+        Some(sorted_differences(a, b).iter().map(|d| d*d).sum::<f64>().sqrt())
     }
-    total_distance
+}
+
+struct MaxDeviation;
+
+impl Metric for MaxDeviation {
+    fn name(&self) -> &str { "Max deviation" }
+
+    fn compare(&self, a: &[i32], b: &[i32]) -> Option<f64> {
+        Some(sorted_differences(a, b).into_iter().fold(0.0, f64::max))
+    }
+}
+
+/// Spearman's rank correlation between the lists, pairing IDs on the same line.
+struct RankCorrelation;
+
+impl RankCorrelation {
+    // Tied IDs share the average of the ranks they span.
+    fn ranks(ids: &[i32]) -> Vec<f64> {
+        let mut order: Vec<usize> = (0..ids.len()).collect();
+        order.sort_by_key(|&i| ids[i]);
+
+        let mut ranks = vec![0.0; ids.len()];
+        let mut start = 0;
+        while start < order.len() {
+            let mut end = start;
+            while end + 1 < order.len() && ids[order[end+1]] == ids[order[start]] {
+                end += 1;
+            }
+            let rank = (start + end) as f64 / 2.0 + 1.0;
+            for &i in order[start..=end].iter() {
+                ranks[i] = rank;
+            }
+            start = end + 1;
+        }
+        ranks
+    }
+}
+
+impl Metric for RankCorrelation {
+    fn name(&self) -> &str { "Rank correlation" }
+
+    fn compare(&self, a: &[i32], b: &[i32]) -> Option<f64> {
+        assert_eq!(a.len(), b.len());
+        let ra = Self::ranks(a);
+        let rb = Self::ranks(b);
+        let n = a.len() as f64;
+        let mean_a = ra.iter().sum::<f64>() / n;
+        let mean_b = rb.iter().sum::<f64>() / n;
+
+        let mut cov = 0.0;
+        let mut var_a = 0.0;
+        let mut var_b = 0.0;
+        for (x, y) in ra.iter().zip(rb.iter()) {
+            cov += (x - mean_a) * (y - mean_b);
+            var_a += (x - mean_a) * (x - mean_a);
+            var_b += (y - mean_b) * (y - mean_b);
+        }
+        // There is nothing to correlate with a list of identical IDs.
+        if var_a == 0.0 || var_b == 0.0 {
+            return None;
+        }
+        Some(cov / (var_a * var_b).sqrt())
+    }
+}
+
+fn comparison_matrix(lists: &[Vec<i32>], metric: &dyn Metric) -> Vec<Vec<Option<f64>>> {
+    let mut matrix = vec![vec![None; lists.len()]; lists.len()];
+    for i in 0..lists.len() {
+        for j in 0..lists.len() {
+            matrix[i][j] = metric.compare(&lists[i], &lists[j]);
+        }
+    }
+    matrix
+}
+
+fn format_matrix(metric: &dyn Metric, matrix: &[Vec<Option<f64>>]) -> String {
+    let mut s = format!("{}\n{:>6}", metric.name(), "");
+    for j in 0..matrix.len() {
+        s.push_str(&format!("{:>14}", format!("list {}", j + 1)));
+    }
+    s.push('\n');
+    for (i, row) in matrix.iter().enumerate() {
+        s.push_str(&format!("{:>6}", format!("{}", i + 1)));
+        for v in row.iter() {
+            match v {
+                Some(v) => s.push_str(&format!("{:>14.3}", v)),
+                None => s.push_str(&format!("{:>14}", "-")),
+            }
+        }
+        s.push('\n');
+    }
+    s
+}
+
+fn matrix_json(metric: &dyn Metric, matrix: &[Vec<Option<f64>>]) -> String {
+    let rows: Vec<String> = matrix.iter()
+        .map(|row| {
+            let values: Vec<String> = row.iter()
+                .map(|v| v.map_or("null".to_string(), |v| v.to_string()))
+                .collect();
+            format!("[{}]", values.join(","))
+        })
+        .collect();
+    format!("{{\"metric\":{},\"matrix\":[{}]}}", json_string(metric.name()), rows.join(","))
+}
+
+fn part1(file_path: &str) -> i32 {
+    let (group_one_ids, group_two_ids) = parse_input(file_path);
+    total_distance(&group_one_ids, &group_two_ids)
 }
 
 fn part2(file_path: &str) -> i32 {
     let (group_one_ids, group_two_ids) = parse_input(file_path);
+    let index = SimilarityIndex::from_lists(&group_one_ids, &group_two_ids);
+    index.similarity_score() as i32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Scores {
+    similarity_score: i64,
+    // Only defined while both lists have the same length.
+    total_distance: Option<i64>,
+}
+
+/// Appearance counts for both lists, kept up to date as IDs are inserted and
+/// removed. The similarity score is updated incrementally; the total distance
+/// is recomputed by walking both (already sorted) maps.
+#[derive(Debug, Default)]
+struct SimilarityIndex {
+    appearances: [BTreeMap<i32, i64>; 2],
+    lengths: [usize; 2],
+    similarity_score: i64,
+}
+
+impl SimilarityIndex {
+    fn from_lists(left: &[i32], right: &[i32]) -> Self {
+        let mut index = Self::default();
+        for &id in left.iter() {
+            index.add(Side::Left, id);
+        }
+        for &id in right.iter() {
+            index.add(Side::Right, id);
+        }
+        index
+    }
+
+    fn similarity_score(&self) -> i64 {
+        self.similarity_score
+    }
+
+    fn total_distance(&self) -> Option<i64> {
+        if self.lengths[0] != self.lengths[1] { return None; }
+
+        let mut left = self.appearances[0].iter().map(|(&id, &n)| (id, n));
+        let mut right = self.appearances[1].iter().map(|(&id, &n)| (id, n));
+        let mut a = left.next();
+        let mut b = right.next();
+        let mut total_distance = 0;
+
+        // Pair up runs of equal IDs instead of expanding the lists.
+        while let (Some((x, nx)), Some((y, ny))) = (a, b) {
+            let n = nx.min(ny);
+            total_distance += n * (x as i64 - y as i64).abs();
+            a = if nx > n { Some((x, nx - n)) } else { left.next() };
+            b = if ny > n { Some((y, ny - n)) } else { right.next() };
+        }
+        Some(total_distance)
+    }
+
+    fn scores(&self) -> Scores {
+        Scores {
+            similarity_score: self.similarity_score(),
+            total_distance: self.total_distance(),
+        }
+    }
+
+    fn insert(&mut self, side: Side, id: i32) -> Scores {
+        self.add(side, id);
+        self.scores()
+    }
+
+    /// Returns `None` if the ID isn't in that list.
+    fn remove(&mut self, side: Side, id: i32) -> Option<Scores> {
+        let (this, other) = Self::sides(side);
+        let count = self.appearances[this].get_mut(&id)?;
+        *count -= 1;
+        if *count == 0 {
+            self.appearances[this].remove(&id);
+        }
+        self.lengths[this] -= 1;
+        self.similarity_score -= id as i64 * self.count(other, id);
+        Some(self.scores())
+    }
 
-    let mut similarity_score = 0;
-    let mut appearances: HashMap<i32, i32> = HashMap::new();
+    fn add(&mut self, side: Side, id: i32) {
+        let (this, other) = Self::sides(side);
+        *self.appearances[this].entry(id).or_insert(0) += 1;
+        self.lengths[this] += 1;
+        self.similarity_score += id as i64 * self.count(other, id);
+    }
+
+    fn count(&self, side: usize, id: i32) -> i64 {
+        *self.appearances[side].get(&id).unwrap_or(&0)
+    }
 
-    for &n in group_two_ids.iter() {
-        if let Some(v) = appearances.get(&n) {
-            appearances.insert(n, v + 1);
-        } else {
-            appearances.insert(n, 1);
+    fn sides(side: Side) -> (usize, usize) {
+        match side {
+            Side::Left => (0, 1),
+            Side::Right => (1, 0),
         }
     }
+}
+
+/// Parses a what-if change such as `+L 3` (insert 3 in the left list) or
+/// `-R 4` (remove 4 from the right list).
+fn parse_change(s: &str) -> Option<(bool, Side, i32)> {
+    let (op, id) = s.trim().split_once(' ')?;
+    let mut chars = op.chars();
+    let insert = match chars.next()? {
+        '+' => true,
+        '-' => false,
+        _ => return None,
+    };
+    let side = match chars.next()? {
+        'L' => Side::Left,
+        'R' => Side::Right,
+        _ => return None,
+    };
+    Some((insert, side, id.trim().parse().ok()?))
+}
+
+fn change_json(change: &str, scores: Option<Scores>) -> String {
+    match scores {
+        Some(Scores { similarity_score, total_distance }) => format!(
+            "{{\"change\":{},\"similarity_score\":{},\"total_distance\":{}}}",
+            json_string(change), similarity_score, total_distance.map_or("null".to_string(), |d| d.to_string())
+        ),
+        None => format!("{{\"change\":{},\"error\":\"ID not in list\"}}", json_string(change)),
+    }
+}
+
+fn parse_pair(line: &str) -> io::Result<(i32, i32)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Invalid line: {}", line.trim()));
+    let ids: Vec<&str> = line.split_whitespace().collect();
+    if ids.len() != 2 { return Err(invalid()); }
+    let parse = |id: &str| i32::from_str_radix(id, 10).map_err(|_| invalid());
+    Ok((parse(ids[0])?, parse(ids[1])?))
+}
+
+/// Similarity score in a single pass. Each new ID is matched against the IDs
+/// already seen in the other list, so memory only grows with distinct IDs.
+fn stream_similarity_score(mut reader: impl BufRead) -> io::Result<i64> {
+    let mut left_counts: HashMap<i32, i64> = HashMap::new();
+    let mut right_counts: HashMap<i32, i64> = HashMap::new();
+    let mut similarity_score: i64 = 0;
+    let mut line = String::new();
 
-    for &m in group_one_ids.iter() {
-        if let Some(v) = appearances.get(&m) {
-            similarity_score += m * v;
+    while reader.read_line(&mut line)? > 0 {
+        if !line.trim().is_empty() {
+            let (a, b) = parse_pair(&line)?;
+            similarity_score += a as i64 * right_counts.get(&a).unwrap_or(&0);
+            *left_counts.entry(a).or_insert(0) += 1;
+            similarity_score += b as i64 * left_counts.get(&b).unwrap_or(&0);
+            *right_counts.entry(b).or_insert(0) += 1;
         }
+        line.clear();
+    }
+    Ok(similarity_score)
+}
+
+/// Total distance using an external sort: both lists are read in chunks of
+/// `chunk_size` lines, each chunk is sorted and spilled to a file in
+/// `spill_dir`, and the sorted runs are merged back while summing.
+fn stream_total_distance(
+    mut reader: impl BufRead,
+    chunk_size: usize,
+    spill_dir: &Path
+) -> io::Result<i64> {
+    assert!(chunk_size > 0);
+    let runs = RunDir::create(spill_dir.join(format!("day01-runs-{}", std::process::id())))?;
+    let run_dir = &runs.0;
+
+    let mut left_runs = Vec::new();
+    let mut right_runs = Vec::new();
+    let mut left = Vec::with_capacity(chunk_size);
+    let mut right = Vec::with_capacity(chunk_size);
+    let mut line = String::new();
+
+    loop {
+        let eof = reader.read_line(&mut line)? == 0;
+        if !line.trim().is_empty() {
+            let (a, b) = parse_pair(&line)?;
+            left.push(a);
+            right.push(b);
+        }
+        line.clear();
+
+        if left.len() == chunk_size || (eof && !left.is_empty()) {
+            let n = left_runs.len();
+            left_runs.push(spill_run(&mut left, run_dir.join(format!("left-{}", n)))?);
+            right_runs.push(spill_run(&mut right, run_dir.join(format!("right-{}", n)))?);
+        }
+        if eof { break; }
+    }
+
+    let mut total_distance: i64 = 0;
+    let left_ids = MergedRuns::open(&left_runs)?;
+    let right_ids = MergedRuns::open(&right_runs)?;
+    for (a, b) in left_ids.zip(right_ids) {
+        total_distance += (a as i64 - b as i64).abs();
+    }
+    Ok(total_distance)
+}
+
+/// Directory for the sorted runs, removed again however the sort ends.
+struct RunDir(PathBuf);
+
+impl RunDir {
+    fn create(path: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for RunDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn spill_run(ids: &mut Vec<i32>, path: PathBuf) -> io::Result<PathBuf> {
+    ids.sort();
+    let mut w = BufWriter::new(File::create(&path)?);
+    for id in ids.iter() {
+        w.write_all(&id.to_le_bytes())?;
+    }
+    w.flush()?;
+    ids.clear();
+    Ok(path)
+}
+
+/// K-way merge of sorted runs of little-endian `i32`s.
+struct MergedRuns {
+    runs: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<(i32, usize)>>,
+}
+
+impl MergedRuns {
+    fn open(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut merged = Self { runs: Vec::new(), heap: BinaryHeap::new() };
+        for path in paths.iter() {
+            merged.runs.push(BufReader::new(File::open(path)?));
+            merged.refill(merged.runs.len() - 1);
+        }
+        Ok(merged)
+    }
+
+    fn refill(&mut self, run: usize) {
+        let mut buf = [0u8; 4];
+        if self.runs[run].read_exact(&mut buf).is_ok() {
+            self.heap.push(Reverse((i32::from_le_bytes(buf), run)));
+        }
+    }
+}
+
+impl Iterator for MergedRuns {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        let Reverse((id, run)) = self.heap.pop()?;
+        self.refill(run);
+        Some(id)
     }
-    similarity_score
 }
 
 fn main() {
-    println!("Total distance: {}", part1("src/day01/input.txt"));
-    println!("Similarity score : {}", part2("src/day01/input.txt"));
+    let mut runner = Runner::new(1);
+    let input = runner.input().to_string();
+    runner.parse(|| parse_input(&input));
+
+    if runner.flag("--stream") {
+        let chunk_size = runner.param("chunk_size", 1 << 20);
+        let open = || BufReader::new(File::open(&input).unwrap());
+        runner.part("Total distance", || {
+            stream_total_distance(open(), chunk_size, &env::temp_dir()).unwrap()
+        });
+        runner.part("Similarity score", || stream_similarity_score(open()).unwrap());
+    } else {
+        runner.part("Total distance", || part1(&input));
+        runner.part("Similarity score", || part2(&input));
+    }
+
+    if runner.flag("--what-if") {
+        let (left, right) = parse_input(&input);
+        let mut index = SimilarityIndex::from_lists(&left, &right);
+        // Answers are printed as they come in text mode, JSON collects them.
+        let text = runner.config().output == OutputFormat::Text;
+        if text { println!("Enter changes like '+L 3' or '-R 4':"); }
+
+        let mut answers = Vec::new();
+        for line in io::stdin().lock().lines() {
+            let line = line.unwrap();
+            let scores = match parse_change(&line) {
+                Some((true, side, id)) => Some(index.insert(side, id)),
+                Some((false, side, id)) => index.remove(side, id),
+                None => {
+                    if text { println!("Invalid change: {}", line); }
+                    answers.push(format!("{{\"change\":{},\"error\":\"invalid change\"}}", json_string(&line)));
+                    continue;
+                },
+            };
+            if text {
+                match scores {
+                    Some(Scores { similarity_score, total_distance: Some(d) }) => println!(
+                        "Similarity score: {}, total distance: {}", similarity_score, d
+                    ),
+                    Some(Scores { similarity_score, total_distance: None }) => println!(
+                        "Similarity score: {}, total distance: - (lists differ in length)",
+                        similarity_score
+                    ),
+                    None => println!("ID not in list: {}", line),
+                }
+            }
+            answers.push(change_json(&line, scores));
+        }
+        runner.section("what_if", "", format!("[{}]", answers.join(",")));
+    }
+
+    if runner.flag("--metrics") {
+        let lists = parse_lists(&input);
+        let metrics: [&dyn Metric; 4] = [&L1, &L2, &MaxDeviation, &RankCorrelation];
+        let mut text = String::new();
+        let mut json = Vec::new();
+        for metric in metrics {
+            let matrix = comparison_matrix(&lists, metric);
+            text.push_str(&format!("\n{}", format_matrix(metric, &matrix)));
+            json.push(matrix_json(metric, &matrix));
+        }
+        runner.section("metrics", &text, format!("[{}]", json.join(",")));
+    }
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -73,4 +540,88 @@ mod tests {
     fn test_part2() {
         assert_eq!(31, part2("src/day01/test.txt"));
     }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!((Vec::new(), Vec::new()), parse_input("src/day01/empty.txt"));
+        assert_eq!(0, part1("src/day01/empty.txt"));
+        assert_eq!(0, part2("src/day01/empty.txt"));
+    }
+
+    #[test]
+    fn test_similarity_index() {
+        let (left, right) = parse_input("src/day01/test.txt");
+        let mut index = SimilarityIndex::from_lists(&left, &right);
+        assert_eq!(Scores { similarity_score: 31, total_distance: Some(11) }, index.scores());
+
+        // Another 4 on the left matches the single 4 on the right.
+        let scores = index.insert(Side::Left, 4);
+        assert_eq!(Scores { similarity_score: 35, total_distance: None }, scores);
+        assert_eq!(
+            "{\"change\":\"+L 4\",\"similarity_score\":35,\"total_distance\":null}",
+            change_json("+L 4", Some(scores))
+        );
+
+        // A 3 on the left matched the three 3s on the right.
+        let scores = index.remove(Side::Left, 3).unwrap();
+        assert_eq!(Scores { similarity_score: 26, total_distance: Some(10) }, scores);
+
+        assert_eq!(None, index.remove(Side::Left, 42));
+        assert_eq!("{\"change\":\"-L 42\",\"error\":\"ID not in list\"}", change_json("-L 42", None));
+        assert_eq!(Some((false, Side::Right, 3)), parse_change("-R 3"));
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input = fs::read_to_string("src/day01/test.txt").unwrap();
+        let spill_dir = env::temp_dir().join(format!("day01-test-streaming-{}", std::process::id()));
+
+        // Chunks of two lines force a merge of three runs.
+        let total = stream_total_distance(input.as_bytes(), 2, &spill_dir).unwrap();
+        assert_eq!(11, total);
+        assert_eq!(31, stream_similarity_score(input.as_bytes()).unwrap());
+
+        // A bad line after the first run has been spilled still cleans up.
+        let err = stream_total_distance(&b"1 2\n3 4\n5 6\n7 8 9\n"[..], 2, &spill_dir).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert_eq!(0, fs::read_dir(&spill_dir).unwrap().count());
+        assert!(stream_similarity_score(&b"1\n"[..]).is_err());
+        fs::remove_dir_all(&spill_dir).unwrap();
+    }
+
+    #[test]
+    fn test_comparison_matrix() {
+        let lists = parse_lists("src/day01/test_multi.txt");
+        assert_eq!(3, lists.len());
+
+        let l1 = comparison_matrix(&lists, &L1);
+        assert_eq!(Some(11.0), l1[0][1]);
+        assert_eq!(l1[1][2], l1[2][1]);
+        assert_eq!(Some(0.0), l1[2][2]);
+        assert_eq!(
+            "{\"metric\":\"L1\",\"matrix\":[[0,11],[11,0]]}",
+            matrix_json(&L1, &comparison_matrix(&lists[..2], &L1))
+        );
+
+        assert_eq!(Some(5.0), comparison_matrix(&lists, &MaxDeviation)[0][1]);
+        assert_eq!(Some(35f64.sqrt()), comparison_matrix(&lists, &L2)[0][1]);
+
+        let rho = comparison_matrix(&lists, &RankCorrelation);
+        assert!((rho[0][0].unwrap() - 1.0).abs() < 1e-9);
+        assert!((rho[0][2].unwrap() + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_constant_list() {
+        let lists = vec![vec![3, 3, 3], vec![1, 2, 3]];
+        let rho = comparison_matrix(&lists, &RankCorrelation);
+        assert_eq!(vec![vec![None, None], vec![None, Some(1.0)]], rho);
+        assert_eq!(
+            "Rank correlation\n\
+             \x20             list 1        list 2\n\
+             \x20    1             -             -\n\
+             \x20    2             -         1.000\n",
+            format_matrix(&RankCorrelation, &rho)
+        );
+    }
 }
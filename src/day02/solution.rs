@@ -1,5 +1,7 @@
 use std::fs::File;
 use std::io::Read;
+use std::cmp::Reverse;
+use advent_of_code_2024::runner::{json_string, Runner};
 
 fn parse_input(file_path: &str) -> Vec<Vec<i32>> {
     let mut input = String::new();
@@ -102,9 +104,263 @@ fn is_slowly_increasing_pd(levels: &Vec<i32>) -> bool {
     }
 }
 
+/// Reference implementation of the Problem Dampener: try removing each level.
+fn is_safe_pd_brute_force(levels: &Vec<i32>) -> bool {
+    if is_safe(levels) { return true; }
+
+    for i in 0..levels.len() {
+        let mut removed = levels.clone();
+        removed.remove(i);
+        if !removed.is_empty() && is_safe(&removed) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Enumerates every report of `1..=max_len` levels with values in
+/// `0..=max_value` and returns those where `is_safe_pd`, the brute force and
+/// the general dampener disagree.
+fn cross_check(max_len: usize, max_value: i32) -> Vec<Vec<i32>> {
+    let dampener = Dampener::default();
+    let mut disagreements = Vec::new();
+
+    for len in 1..=max_len {
+        let mut levels = vec![0; len];
+        loop {
+            let expected = is_safe_pd_brute_force(&levels);
+            if is_safe_pd(&levels) != expected || dampener.is_safe(&levels, 1) != expected {
+                disagreements.push(levels.clone());
+            }
+
+            // Advance like an odometer.
+            let mut i = 0;
+            while i < len && levels[i] == max_value {
+                levels[i] = 0;
+                i += 1;
+            }
+            if i == len { break; }
+            levels[i] += 1;
+        }
+    }
+    disagreements
+}
+
+/// Generalized Problem Dampener: a report is safe if, after removing at most
+/// `k` levels, every step is within `min_step..=max_step` in one direction.
+#[derive(Debug, Clone, Copy)]
+struct Dampener {
+    min_step: i32,
+    max_step: i32,
+}
+
+impl Default for Dampener {
+    fn default() -> Self {
+        Self { min_step: 1, max_step: 3 }
+    }
+}
+
+impl Dampener {
+    fn new(min_step: i32, max_step: i32) -> Self {
+        assert!(min_step <= max_step);
+        Self { min_step, max_step }
+    }
+
+    fn is_allowed(&self, distance: i32) -> bool {
+        distance >= self.min_step && distance <= self.max_step
+    }
+
+    fn is_safe(&self, levels: &[i32], k: usize) -> bool {
+        self.min_removals(levels) <= k
+    }
+
+    /// Fewest levels to remove for the report to become safe. At least one
+    /// level is always kept.
+    fn min_removals(&self, levels: &[i32]) -> usize {
+        self.removals(levels).1.len()
+    }
+
+    /// The direction needing the fewest removals, and the indices of the
+    /// levels to remove. Ties go to increasing.
+    fn removals(&self, levels: &[i32]) -> (Direction, Vec<usize>) {
+        let increasing = self.kept_levels(levels, Direction::Increasing);
+        let decreasing = self.kept_levels(levels, Direction::Decreasing);
+        let (direction, kept) = if decreasing.len() > increasing.len() {
+            (Direction::Decreasing, decreasing)
+        } else {
+            (Direction::Increasing, increasing)
+        };
+
+        let removed = (0..levels.len()).filter(|i| !kept.contains(i)).collect();
+        (direction, removed)
+    }
+
+    // Indices of the longest subsequence whose steps are all allowed.
+    // longest_ending[i] is the length of the best one ending in levels[i],
+    // and previous[i] the level before it.
+    fn kept_levels(&self, levels: &[i32], direction: Direction) -> Vec<usize> {
+        let mut longest_ending = vec![1; levels.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; levels.len()];
+
+        for i in 1..levels.len() {
+            for j in 0..i {
+                let step = direction.sign() * (levels[i] - levels[j]);
+                if self.is_allowed(step) && longest_ending[j] + 1 > longest_ending[i] {
+                    longest_ending[i] = longest_ending[j] + 1;
+                    previous[i] = Some(j);
+                }
+            }
+        }
+
+        let mut kept = Vec::new();
+        let mut curr = (0..levels.len()).max_by_key(|&i| (longest_ending[i], Reverse(i)));
+        while let Some(i) = curr {
+            kept.push(i);
+            curr = previous[i];
+        }
+        kept.reverse();
+        kept
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Increasing,
+    Decreasing,
+}
+
+impl Direction {
+    fn sign(&self) -> i32 {
+        match self {
+            Direction::Increasing => 1,
+            Direction::Decreasing => -1,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Direction::Increasing => "increasing",
+            Direction::Decreasing => "decreasing",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Diagnosis {
+    line: usize,
+    levels: Vec<i32>,
+    direction: Direction,
+    // Indices into get_element_distances, i.e. the step from level i to i+1.
+    bad_steps: Vec<usize>,
+    removed: Vec<usize>,
+}
+
+impl Diagnosis {
+    fn new(line: usize, levels: &Vec<i32>, dampener: &Dampener) -> Self {
+        let (direction, removed) = dampener.removals(levels);
+        let bad_steps = get_element_distances(levels).into_iter()
+            .enumerate()
+            .filter(|&(_, d)| !dampener.is_allowed(direction.sign() * d))
+            .map(|(i, _)| i)
+            .collect();
+
+        Self { line, levels: levels.clone(), direction, bad_steps, removed }
+    }
+}
+
+fn format_diagnoses(diagnoses: &[Diagnosis]) -> String {
+    let join = |v: Vec<String>| if v.is_empty() { "-".to_string() } else { v.join(",") };
+
+    let mut s = format!(
+        "{:>5}  {:<30}  {:<10}  {:<12}  {}\n",
+        "line", "levels", "direction", "bad steps", "removed levels"
+    );
+    for d in diagnoses.iter() {
+        let levels = join(d.levels.iter().map(|e| e.to_string()).collect());
+        let direction = d.direction.name();
+        let bad_steps = join(d.bad_steps.iter().map(|i| format!("{}-{}", i, i + 1)).collect());
+        let removed = join(
+            d.removed.iter().map(|&i| format!("{}:{}", i, d.levels[i])).collect()
+        );
+        s.push_str(&format!(
+            "{:>5}  {:<30}  {:<10}  {:<12}  {}\n",
+            d.line, levels, direction, bad_steps, removed
+        ));
+    }
+    s
+}
+
+fn cross_check_json(max_len: usize, max_value: i32, disagreements: &[Vec<i32>]) -> String {
+    let disagreements: Vec<String> = disagreements.iter()
+        .map(|levels| format!("{:?}", levels).replace(' ', ""))
+        .collect();
+    format!(
+        "{{\"max_len\":{},\"max_value\":{},\"disagreements\":[{}]}}",
+        max_len, max_value, disagreements.join(",")
+    )
+}
+
+fn diagnoses_json(diagnoses: &[Diagnosis]) -> String {
+    let list = |v: &[usize]| v.iter().map(|e| e.to_string()).collect::<Vec<String>>().join(",");
+    let diagnoses: Vec<String> = diagnoses.iter()
+        .map(|d| {
+            let levels: Vec<String> = d.levels.iter().map(|e| e.to_string()).collect();
+            format!(
+                "{{\"line\":{},\"levels\":[{}],\"direction\":{},\"bad_steps\":[{}],\"removed\":[{}]}}",
+                d.line, levels.join(","), json_string(d.direction.name()),
+                list(&d.bad_steps), list(&d.removed)
+            )
+        })
+        .collect();
+    format!("[{}]", diagnoses.join(","))
+}
+
+fn count_safe_dampened(file_path: &str, dampener: &Dampener, k: usize) -> usize {
+    let levels = parse_input(file_path);
+    levels.iter().filter(|e| dampener.is_safe(e, k)).count()
+}
+
 fn main() {
-    println!("Safe reports: {}", part1("src/day02/input.txt"));
-    println!("Safe reports w/ Dampener: {}", part2("src/day02/input.txt"));
+    let mut runner = Runner::new(2);
+    let input = runner.input().to_string();
+    runner.parse(|| parse_input(&input));
+    runner.part("Safe reports", || part1(&input));
+    runner.part("Safe reports w/ Dampener", || part2(&input));
+
+    if runner.flag("--cross-check") {
+        let max_len = runner.param("cross_check_len", 7);
+        let max_value = runner.param("cross_check_value", 7);
+        let disagreements = cross_check(max_len, max_value);
+        let mut text = String::new();
+        for levels in disagreements.iter() {
+            text.push_str(&format!("Disagreement: {:?}\n", levels));
+        }
+        text.push_str(&format!(
+            "Cross-checked reports up to {} levels over 0..={}: {} disagreements\n",
+            max_len, max_value, disagreements.len()
+        ));
+        runner.section("cross_check", &text, cross_check_json(max_len, max_value, &disagreements));
+    }
+
+    if runner.flag("--diagnostics") {
+        let dampener = Dampener::new(runner.param("min_step", 1), runner.param("max_step", 3));
+        let diagnoses: Vec<Diagnosis> = parse_input(&input).iter()
+            .enumerate()
+            .map(|(i, levels)| Diagnosis::new(i + 1, levels, &dampener))
+            .collect();
+        runner.section("diagnostics", &format_diagnoses(&diagnoses), diagnoses_json(&diagnoses));
+    }
+
+    if runner.flag("--dampener") {
+        let dampener = Dampener::new(runner.param("min_step", 1), runner.param("max_step", 3));
+        let k = runner.param("max_removals", 1);
+        let label = format!(
+            "Safe reports removing up to {} levels (steps {}..={})",
+            k, dampener.min_step, dampener.max_step
+        );
+        runner.part(&label, || count_safe_dampened(&input, &dampener, k));
+    }
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -120,5 +376,67 @@ mod tests {
     fn test_part2() {
         assert_eq!(4, part2("src/day02/test.txt"));
     }
+
+    #[test]
+    fn test_brute_force() {
+        let reports = parse_input("src/day02/test.txt");
+        let safe: Vec<bool> = reports.iter().map(is_safe_pd_brute_force).collect();
+        assert_eq!(vec![true, false, false, true, true, true], safe);
+    }
+
+    #[test]
+    fn test_cross_check_exhaustive() {
+        assert_eq!(Vec::<Vec<i32>>::new(), cross_check(6, 6));
+        assert_eq!(
+            "{\"max_len\":6,\"max_value\":6,\"disagreements\":[[1,2],[3]]}",
+            cross_check_json(6, 6, &[vec![1, 2], vec![3]])
+        );
+    }
+
+    #[test]
+    fn test_dampener() {
+        let dampener = Dampener::default();
+        let reports = parse_input("src/day02/test.txt");
+        let removals: Vec<usize> = reports.iter().map(|e| dampener.min_removals(e)).collect();
+        assert_eq!(vec![0, 2, 2, 1, 1, 0], removals);
+
+        assert_eq!(2, count_safe_dampened("src/day02/test.txt", &dampener, 0));
+        assert_eq!(4, count_safe_dampened("src/day02/test.txt", &dampener, 1));
+        assert_eq!(6, count_safe_dampened("src/day02/test.txt", &dampener, 2));
+
+        // Allowing flat steps and jumps of up to five.
+        let lenient = Dampener::new(0, 5);
+        assert!(lenient.is_safe(&reports[1], 0));
+        assert!(lenient.is_safe(&reports[4], 0));
+        assert_eq!(1, lenient.min_removals(&reports[3]));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let dampener = Dampener::default();
+        let reports = parse_input("src/day02/test.txt");
+
+        let d = Diagnosis::new(4, &reports[3], &dampener);
+        assert_eq!(Direction::Increasing, d.direction);
+        assert_eq!(vec![1], d.bad_steps);
+        // Removing either the 3 or the 2 works; the later level is dropped.
+        assert_eq!(vec![2], d.removed);
+
+        let d = Diagnosis::new(5, &reports[4], &dampener);
+        assert_eq!(Direction::Decreasing, d.direction);
+        assert_eq!(vec![2], d.bad_steps);
+        assert_eq!(vec![3], d.removed);
+
+        let diagnoses = [d];
+        let table = format_diagnoses(&diagnoses);
+        assert_eq!(
+            "    5  8,6,4,4,1                       decreasing  2-3           3:4",
+            table.lines().nth(1).unwrap()
+        );
+        assert_eq!(
+            "[{\"line\":5,\"levels\":[8,6,4,4,1],\"direction\":\"decreasing\",\"bad_steps\":[2],\"removed\":[3]}]",
+            diagnoses_json(&diagnoses)
+        );
+    }
 }
 
@@ -1,5 +1,10 @@
 use std::io::Read;
 use std::fs::File;
+use std::ops::Range;
+use std::collections::HashMap;
+use advent_of_code_2024::config::OutputFormat;
+use advent_of_code_2024::runner::{json_string, Runner};
+use advent_of_code_2024::search::AhoCorasick;
 
 fn parse_input(file_path: &str) -> String {
     let mut input = String::new();
@@ -11,113 +16,469 @@ fn parse_input(file_path: &str) -> String {
 
 fn part1(file_path: &str) -> i32 {
     let input = parse_input(file_path);
-    let candidates = string_search(&input, "mul(");
-    sum_of_products(&input, &candidates)
 
+    // Without do() and don't() every mul is enabled.
+    let mut interpreter = Interpreter::new();
+    interpreter.register("mul", 2, mul);
+    let program = interpreter.parse(&input);
+    interpreter.run(&program).total
 }
 
-fn sum_of_products(input: &str, candidates: &Vec<usize>) -> i32 {
-    let input_bytes = input.as_bytes();
-    let mut total: i32 = 0;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Keyword(&'static str),
+    LParen,
+    RParen,
+    Comma,
+    Number,
+    Garbage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
 
-    for &i in candidates.iter() {
-        let mut bracket_content = String::new();
-        let mut closed = false;
-        let mut counter = 4; // Start after open bracket
+/// Splits the input into tokens, recognizing the given instruction names as
+/// keywords. Where names overlap the longest one wins, so that "don't" isn't
+/// lexed as "do".
+fn tokenize(input: &str, keywords: &[&'static str]) -> Vec<Token> {
+    let bytes = input.as_bytes();
+    // Find all keywords up front in a single pass.
+    let keyword_at = AhoCorasick::new(keywords).longest_at(bytes);
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
 
-        while i + counter < input.len() {
-            let curr_char = input_bytes[i+counter] as char;
-            match curr_char {
-                '0'..='9' | ',' => {
-                    bracket_content.push(curr_char);
-                },
-                ')' => {
-                    closed = true;
-                    break;
+    while i < bytes.len() {
+        let start = i;
+        let kind = if let Some(k) = keyword_at[i] {
+            let keyword = keywords[k];
+            i += keyword.len();
+            TokenKind::Keyword(keyword)
+        } else {
+            i += 1;
+            match bytes[start] {
+                b'(' => TokenKind::LParen,
+                b')' => TokenKind::RParen,
+                b',' => TokenKind::Comma,
+                b'0'..=b'9' => {
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    TokenKind::Number
                 },
-                _ => { break; }
+                _ => TokenKind::Garbage,
             }
-            counter += 1;
-        }
-        if !closed { continue; }
+        };
 
-        total += bracket_content
-                    .split(',')
-                    .map(|e| i32::from_str_radix(e, 10).unwrap_or(0))
-                    .product::<i32>();
+        // Merge runs of garbage into a single token.
+        match tokens.last_mut() {
+            Some(prev) if kind == TokenKind::Garbage && prev.kind == kind => {
+                prev.span.end = i;
+            },
+            _ => tokens.push(Token { kind, span: start..i }),
+        }
     }
-    total
+    tokens
 }
 
-fn string_search(s: &str, p: &str) -> Vec<usize> {
-    let mut indices: Vec<usize> = Vec::new();
-    if s.len() < p.len() { return indices; }
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Instruction {
+    Mul(i32, i32),
+    Do,
+    Dont,
+    Custom(&'static str, Vec<i32>),
+}
 
-    let s_bytes = s.as_bytes();
-    let p_bytes = p.as_bytes();
-    let length = s_bytes.len() - p_bytes.len() + 1;
+impl Instruction {
+    fn from_call(name: &'static str, args: Vec<i32>) -> Self {
+        match (name, args.as_slice()) {
+            ("mul", &[a, b]) => Instruction::Mul(a, b),
+            ("do", &[]) => Instruction::Do,
+            ("don't", &[]) => Instruction::Dont,
+            _ => Instruction::Custom(name, args),
+        }
+    }
 
-    for i in 0..length {
-        let mut found_match = true;
-        for j in 0..p_bytes.len() {
-            if s_bytes[i+j] != p_bytes[j] {
-                found_match = false;
-                break;
-            }
+    fn name(&self) -> &'static str {
+        match self {
+            Instruction::Mul(..) => "mul",
+            Instruction::Do => "do",
+            Instruction::Dont => "don't",
+            Instruction::Custom(name, _) => name,
         }
-        if found_match {
-            indices.push(i);
+    }
+
+    fn args(&self) -> Vec<i32> {
+        match self {
+            Instruction::Mul(a, b) => vec![*a, *b],
+            Instruction::Do | Instruction::Dont => vec![],
+            Instruction::Custom(_, args) => args.clone(),
         }
     }
-    indices
 }
 
-fn part2(file_path: &str) -> i32 {
-    let input = parse_input(file_path);
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spanned {
+    instruction: Instruction,
+    span: Range<usize>,
+}
 
-    let dos = string_search(&input, "do()");
-    let donts = string_search(&input, "don't()");
-    let muls = string_search(&input, "mul(");
-    let candidates = filter_muls(&muls, &dos, &donts);
+/// Parses `name(arg,...)` calls for the given `(name, arity)` pairs, skipping
+/// anything that doesn't match exactly.
+fn parse_instructions(input: &str, specs: &[(&'static str, usize)]) -> Vec<Spanned> {
+    let keywords: Vec<&'static str> = specs.iter().map(|&(name, _)| name).collect();
+    let tokens = tokenize(input, &keywords);
+    let mut instructions = Vec::new();
+    let mut i = 0;
 
-    sum_of_products(&input, &candidates)
+    while i < tokens.len() {
+        match parse_instruction(input, &tokens[i..], specs) {
+            Some((instruction, len)) => {
+                let span = tokens[i].span.start..tokens[i + len - 1].span.end;
+                instructions.push(Spanned { instruction, span });
+                i += len;
+            },
+            None => { i += 1; },
+        }
+    }
+    instructions
 }
 
-fn filter_muls(muls: &Vec<usize>, dos: &Vec<usize>, donts: &Vec<usize>) -> Vec<usize> {
-    let mut filtered = Vec::new();
+/// Parses an instruction at the start of `tokens`, returning it along with the
+/// number of tokens it spans.
+fn parse_instruction(
+    input: &str,
+    tokens: &[Token],
+    specs: &[(&'static str, usize)]
+) -> Option<(Instruction, usize)> {
+    let name = match tokens.first()?.kind {
+        TokenKind::Keyword(name) => name,
+        _ => return None,
+    };
+    let &(_, arity) = specs.iter().find(|&&(n, _)| n == name)?;
 
-    let mut dos_i = 0;
-    let mut donts_i = 0;
+    let mut it = tokens[1..].iter();
+    let mut expect = |kind: TokenKind| it.next().filter(|t| t.kind == kind);
 
-    for &m in muls.iter() {
-        if m == 0 { filtered.push(m); }
+    expect(TokenKind::LParen)?;
+    let mut args = Vec::with_capacity(arity);
+    for j in 0..arity {
+        if j > 0 { expect(TokenKind::Comma)?; }
+        let t = expect(TokenKind::Number)?;
+        // Arguments are 1-3 digit numbers.
+        if t.span.len() > 3 { return None; }
+        args.push(i32::from_str_radix(&input[t.span.clone()], 10).ok()?);
+    }
+    expect(TokenKind::RParen)?;
 
-        while dos[dos_i] < m {
-            if dos_i+1 == dos.len() || dos[dos_i+1] >= m { 
-                break;
-            }
-            dos_i += 1;
+    // Keyword, brackets and the arguments with commas between them.
+    let len = 3 + if arity == 0 { 0 } else { 2 * arity - 1 };
+    Some((Instruction::from_call(name, args), len))
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    total: i32,
+    enabled: bool,
+    // Number of unmatched don't() calls when toggles nest.
+    depth: usize,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { total: 0, enabled: true, depth: 0 }
+    }
+}
+
+type Handler = fn(&mut State, &[i32]);
+
+fn mul(state: &mut State, args: &[i32]) {
+    if state.enabled {
+        state.total += args[0] * args[1];
+    }
+}
+
+fn enable(state: &mut State, _: &[i32]) {
+    state.enabled = true;
+}
+
+fn disable(state: &mut State, _: &[i32]) {
+    state.enabled = false;
+}
+
+fn enable_nested(state: &mut State, _: &[i32]) {
+    state.depth = state.depth.saturating_sub(1);
+    state.enabled = state.depth == 0;
+}
+
+fn disable_nested(state: &mut State, _: &[i32]) {
+    state.depth += 1;
+    state.enabled = false;
+}
+
+/// Executes parsed instructions by dispatching on their name to registered
+/// handlers. Registering a name again replaces its handler.
+struct Interpreter {
+    specs: Vec<(&'static str, usize)>,
+    handlers: HashMap<&'static str, Handler>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        let mut interpreter = Self::new();
+        interpreter.register("mul", 2, mul)
+            .register("do", 0, enable)
+            .register("don't", 0, disable);
+        interpreter
+    }
+}
+
+impl Interpreter {
+    fn new() -> Self {
+        Self { specs: Vec::new(), handlers: HashMap::new() }
+    }
+
+    fn register(&mut self, name: &'static str, arity: usize, handler: Handler) -> &mut Self {
+        self.specs.retain(|&(n, _)| n != name);
+        self.specs.push((name, arity));
+        self.handlers.insert(name, handler);
+        self
+    }
+
+    fn keywords(&self) -> Vec<&'static str> {
+        self.specs.iter().map(|&(name, _)| name).collect()
+    }
+
+    fn parse(&self, input: &str) -> Vec<Spanned> {
+        parse_instructions(input, &self.specs)
+    }
+
+    fn step(&self, state: &mut State, instruction: &Instruction) {
+        let handler = self.handlers[instruction.name()];
+        handler(state, &instruction.args());
+    }
+
+    fn run(&self, program: &[Spanned]) -> State {
+        let mut state = State::default();
+        for e in program.iter() {
+            self.step(&mut state, &e.instruction);
         }
+        state
+    }
+}
 
-        while donts[donts_i] < m {
-            if donts_i+1 == donts.len() || donts[donts_i+1] >= m { 
-                break;
-            }
-            donts_i += 1;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Enabled,
+    Disabled,
+    // Any other instruction, e.g. do() and don't().
+    Control,
+    // A keyword that didn't parse, with what looked like its arguments.
+    NearMiss,
+}
+
+impl Mark {
+    fn name(&self) -> &'static str {
+        match self {
+            Mark::Enabled => "enabled",
+            Mark::Disabled => "disabled",
+            Mark::Control => "control",
+            Mark::NearMiss => "near-miss",
         }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Annotation {
+    mark: Mark,
+    span: Range<usize>,
+    // Product of a mul, whether it was counted or not.
+    product: Option<i32>,
+}
 
-        if donts[donts_i] > m {
-            filtered.push(m); 
-        } else if dos[dos_i] < m && dos[dos_i] > donts[donts_i] {
-            filtered.push(m); 
+/// Runs the program in `input`, marking every instruction and near miss in
+/// the order they appear.
+fn annotate(interpreter: &Interpreter, input: &str) -> Vec<Annotation> {
+    let program = interpreter.parse(input);
+    let mut annotations = Vec::new();
+    let mut state = State::default();
+
+    for e in program.iter() {
+        let (mark, product) = match e.instruction {
+            Instruction::Mul(a, b) if state.enabled => (Mark::Enabled, Some(a * b)),
+            Instruction::Mul(a, b) => (Mark::Disabled, Some(a * b)),
+            _ => (Mark::Control, None),
+        };
+        interpreter.step(&mut state, &e.instruction);
+        annotations.push(Annotation { mark, span: e.span.clone(), product });
+    }
+
+    let tokens = tokenize(input, &interpreter.keywords());
+    let mut i = 0;
+    while i < tokens.len() {
+        let start = tokens[i].span.start;
+        let parsed = program.binary_search_by_key(&start, |e| e.span.start).is_ok();
+        if !matches!(tokens[i].kind, TokenKind::Keyword(_)) || parsed {
+            i += 1;
+            continue;
         }
+
+        let mut j = i + 1;
+        while j < tokens.len() && matches!(
+            tokens[j].kind,
+            TokenKind::LParen | TokenKind::RParen | TokenKind::Comma | TokenKind::Number
+        ) {
+            j += 1;
+        }
+        let span = start..tokens[j - 1].span.end;
+        annotations.push(Annotation { mark: Mark::NearMiss, span, product: None });
+        i = j;
     }
-    filtered
+
+    annotations.sort_by_key(|a| a.span.start);
+    annotations
+}
+
+/// Re-emits the input with every annotated span wrapped by `mark`.
+fn render(
+    input: &str,
+    annotations: &[Annotation],
+    plain: impl Fn(&str) -> String,
+    mark: impl Fn(Mark, &str) -> String
+) -> String {
+    let mut s = String::new();
+    let mut pos = 0;
+    for a in annotations.iter() {
+        s.push_str(&plain(&input[pos..a.span.start]));
+        s.push_str(&mark(a.mark, &input[a.span.clone()]));
+        pos = a.span.end;
+    }
+    s.push_str(&plain(&input[pos..]));
+    s
+}
+
+fn render_ansi(input: &str, annotations: &[Annotation]) -> String {
+    render(input, annotations, |text| text.to_string(), |mark, text| {
+        let colour = match mark {
+            Mark::Enabled => "1;32",
+            Mark::Disabled => "31",
+            Mark::Control => "1;34",
+            Mark::NearMiss => "33",
+        };
+        format!("\x1b[{}m{}\x1b[0m", colour, text)
+    })
+}
+
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn render_html(input: &str, annotations: &[Annotation]) -> String {
+    let body = render(input, annotations, html_escape, |mark, text| {
+        format!(
+            "<span class=\"{}\" title=\"{}\">{}</span>",
+            mark.name(), mark.name(), html_escape(text)
+        )
+    });
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<style>\n\
+         pre {{ white-space: pre-wrap; word-break: break-all; color: #888; }}\n\
+         .enabled {{ color: #080; font-weight: bold; }}\n\
+         .disabled {{ color: #c00; text-decoration: line-through; }}\n\
+         .control {{ color: #00c; font-weight: bold; }}\n\
+         .near-miss {{ color: #b80; }}\n\
+         </style>\n</head>\n<body>\n<pre>{}</pre>\n</body>\n</html>\n",
+        body
+    )
+}
+
+fn format_annotations(input: &str, annotations: &[Annotation]) -> String {
+    let mut s = format!("{:>7}  {:<16}  {:<10}  {:>7}\n", "offset", "text", "status", "product");
+    for a in annotations.iter() {
+        let product = match a.product {
+            Some(p) => p.to_string(),
+            None => "-".to_string(),
+        };
+        s.push_str(&format!(
+            "{:>7}  {:<16}  {:<10}  {:>7}\n",
+            a.span.start, &input[a.span.clone()], a.mark.name(), product
+        ));
+    }
+    s
+}
+
+fn annotations_json(input: &str, annotations: &[Annotation]) -> String {
+    let annotations: Vec<String> = annotations.iter()
+        .map(|a| {
+            let product = match a.product {
+                Some(p) => p.to_string(),
+                None => "null".to_string(),
+            };
+            format!(
+                "{{\"offset\":{},\"text\":{},\"status\":{},\"product\":{}}}",
+                a.span.start, json_string(&input[a.span.clone()]), json_string(a.mark.name()), product
+            )
+        })
+        .collect();
+    format!("[{}]", annotations.join(","))
+}
+
+fn part2(file_path: &str) -> i32 {
+    let input = parse_input(file_path);
+    let interpreter = Interpreter::default();
+    let program = interpreter.parse(&input);
+    interpreter.run(&program).total
+}
+
+/// Like part 2, but every don't() has to be undone by its own do().
+fn sum_nested(file_path: &str) -> i32 {
+    let input = parse_input(file_path);
+    let mut interpreter = Interpreter::default();
+    interpreter.register("do", 0, enable_nested)
+        .register("don't", 0, disable_nested);
+    let program = interpreter.parse(&input);
+    interpreter.run(&program).total
 }
 
 fn main() {
-    println!("Sum of multiplications: {}", part1("src/day03/input.txt"));
-    println!("Sum of enabled multiplications: {}", part2("src/day03/input.txt"));
+    let mut runner = Runner::new(3);
+    let input = runner.input().to_string();
+    runner.parse(|| parse_input(&input));
+    runner.part("Sum of multiplications", || part1(&input));
+    runner.part("Sum of enabled multiplications", || part2(&input));
+    if runner.flag("--nested") {
+        runner.part("Sum of enabled multiplications (nested)", || sum_nested(&input));
+    }
+
+    if runner.flag("--annotate") {
+        let memory = parse_input(&input);
+        let annotations = annotate(&Interpreter::default(), &memory);
+        let html = runner.flag("--html");
+        // The rendered memory is only shown as text; JSON gets the listing.
+        if runner.config().output == OutputFormat::Text {
+            if html {
+                print!("{}", render_html(&memory, &annotations));
+            } else {
+                println!("{}", render_ansi(&memory, &annotations));
+            }
+        }
+        let listing = if html { String::new() } else { format_annotations(&memory, &annotations) };
+        runner.section("annotations", &listing, annotations_json(&memory, &annotations));
+    }
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -133,4 +494,103 @@ mod tests {
     fn test_part2() {
         assert_eq!(48, part2("src/day03/test2.txt"));
     }
+
+    #[test]
+    fn test_parse_instructions() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()";
+        let expected = vec![
+            Spanned { instruction: Instruction::Mul(2, 4), span: 1..9 },
+            Spanned { instruction: Instruction::Dont, span: 20..27 },
+            Spanned { instruction: Instruction::Mul(5, 5), span: 28..36 },
+            Spanned { instruction: Instruction::Mul(11, 8), span: 48..57 },
+            Spanned { instruction: Instruction::Do, span: 59..63 },
+        ];
+        assert_eq!(expected, Interpreter::default().parse(input));
+    }
+
+    #[test]
+    fn test_malformed_arguments() {
+        let input = "mul(1,2,3)mul(,5)mul(1234,5)mul(12,345)mul( 1,2)mul(4,5";
+        let instructions: Vec<Instruction> = Interpreter::default().parse(input).into_iter()
+            .map(|e| e.instruction)
+            .collect();
+        assert_eq!(vec![Instruction::Mul(12, 345)], instructions);
+    }
+
+    #[test]
+    fn test_no_toggles() {
+        // Used to panic indexing into the empty list of do() positions.
+        let interpreter = Interpreter::default();
+        assert_eq!(8, interpreter.run(&interpreter.parse("mul(2,4)")).total);
+        assert_eq!(0, interpreter.run(&interpreter.parse("don't()mul(2,4)")).total);
+    }
+
+    #[test]
+    fn test_annotate() {
+        let input = parse_input("src/day03/test2.txt");
+        let annotations = annotate(&Interpreter::default(), &input);
+        let marks: Vec<(usize, Mark, Option<i32>)> = annotations.iter()
+            .map(|a| (a.span.start, a.mark, a.product))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, Mark::Enabled, Some(8)),
+                (10, Mark::NearMiss, None),
+                (20, Mark::Control, None),
+                (28, Mark::Disabled, Some(25)),
+                (37, Mark::NearMiss, None),
+                (48, Mark::Disabled, Some(88)),
+                (59, Mark::Control, None),
+                (64, Mark::Enabled, Some(40)),
+            ],
+            marks
+        );
+        assert_eq!("mul(32,64", &input[annotations[4].span.clone()]);
+
+        let listing = format_annotations(&input, &annotations);
+        assert_eq!(
+            "     37  mul(32,64         near-miss         -",
+            listing.lines().nth(5).unwrap()
+        );
+        assert_eq!(
+            "[{\"offset\":1,\"text\":\"mul(2,4)\",\"status\":\"enabled\",\"product\":8},\
+             {\"offset\":10,\"text\":\"mul\",\"status\":\"near-miss\",\"product\":null}]",
+            annotations_json(&input, &annotations[..2])
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "x<mul(2,4)don't()mul(1,1]";
+        let annotations = annotate(&Interpreter::default(), input);
+        assert_eq!(
+            "x<\x1b[1;32mmul(2,4)\x1b[0m\x1b[1;34mdon't()\x1b[0m\x1b[33mmul(1,1\x1b[0m]",
+            render_ansi(input, &annotations)
+        );
+        assert!(render_html(input, &annotations).contains(
+            "x&lt;<span class=\"enabled\" title=\"enabled\">mul(2,4)</span>\
+             <span class=\"control\" title=\"control\">don&#39;t()</span>"
+        ));
+    }
+
+    #[test]
+    fn test_custom_instructions() {
+        let mut interpreter = Interpreter::default();
+        interpreter
+            .register("add", 2, |state, args| {
+                if state.enabled { state.total += args[0] + args[1]; }
+            })
+            .register("reset", 0, |state, _| state.total = 0)
+            .register("do", 0, enable_nested)
+            .register("don't", 0, disable_nested);
+
+        let program = interpreter.parse(
+            "mul(2,3)reset()add(1,2)don't()don't()mul(5,5)do()add(9,9)do()mul(2,2)"
+        );
+        assert_eq!(
+            Instruction::Custom("add", vec![1, 2]),
+            program[2].instruction
+        );
+        assert_eq!(7, interpreter.run(&program).total);
+    }
 }
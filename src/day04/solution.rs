@@ -1,10 +1,39 @@
 use std::{fs::File, io::Read};
+use std::str::FromStr;
+use advent_of_code_2024::runner::{json_string, Runner};
+
+/// What happens when a search runs off the side of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Edges {
+    #[default]
+    Bounded,
+    /// Leaving on the left comes back on the right and vice versa.
+    WrapX,
+    /// Leaving at the top comes back at the bottom and vice versa.
+    WrapY,
+    Torus,
+}
+
+impl FromStr for Edges {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bounded" => Ok(Edges::Bounded),
+            "wrap-x" => Ok(Edges::WrapX),
+            "wrap-y" => Ok(Edges::WrapY),
+            "torus" => Ok(Edges::Torus),
+            _ => Err(format!("unknown edge mode '{}'", s)),
+        }
+    }
+}
 
 #[derive(Debug)]
 struct Board {
     es: Vec<char>,
     rows: usize,
     cols: usize,
+    edges: Edges,
 }
 
 impl Board {
@@ -13,128 +42,440 @@ impl Board {
         let mut f = File::open(file_path).unwrap();
         f.read_to_string(&mut input).unwrap();
 
-        let mut es: Vec<char> = input.chars().collect();
-        es.retain(|&e| e != '\n');
-        let cols = input.lines().count();
-        let rows = es.len() / cols;
+        let lines: Vec<&str> = input.lines().collect();
+        let rows = lines.len();
+        let cols = lines[0].chars().count();
+        assert!(
+            lines.iter().all(|e| e.chars().count() == cols),
+            "Board rows differ in length"
+        );
+        let es: Vec<char> = lines.iter().flat_map(|e| e.chars()).collect();
 
-        Self { es, rows, cols, }
+        Self { es, rows, cols, edges: Edges::Bounded }
+    }
+
+    fn with_edges(mut self, edges: Edges) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// Index of the cell at (x, y), wrapping around the edges the board is
+    /// set up to wrap around.
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let (cols, rows) = (self.cols as i32, self.rows as i32);
+        let (x, y) = match self.edges {
+            Edges::Bounded => (x, y),
+            Edges::WrapX => (x.rem_euclid(cols), y),
+            Edges::WrapY => (x, y.rem_euclid(rows)),
+            Edges::Torus => (x.rem_euclid(cols), y.rem_euclid(rows)),
+        };
+
+        // Check the bounds!
+        let x_oob = x < 0 || x >= cols;
+        let y_oob = y < 0 || y >= rows;
+        if x_oob || y_oob { return None; }
+        Some(x as usize + (y as usize)*self.cols)
     }
 }
 
-fn part1(file_path: &str) -> i32 {
-    let board = Board::from_file(file_path); 
-    let mut total_matches: i32 = 0;
-    let word = "XMAS";
-    let word_chars: Vec<char> = word.chars().collect();
-
-    for i in 0..board.es.len() {
-        if board.es[i] != word_chars[0] { continue; }
-
-        let x_pos = i % board.cols;
-        let y_pos = i / board.rows;
-        
-        for x_offset in -1..=1 {
-            for y_offset in -1..=1 {
-                if x_offset == 0 && y_offset == 0 { continue; }
-                let mut match_found = true; 
-                let mut count: i32 = 1;
-
-                while count < word_chars.len() as i32 {
-                    let new_x: i32 = x_pos as i32 + x_offset*count;
-                    let new_y: i32 = y_pos as i32 + y_offset*count;
-                    // Check the bounds!
-                    let x_oob = new_x < 0 || new_x >= board.cols as i32;
-                    let y_oob = new_y < 0 || new_y >= board.rows as i32;
-
-                    if x_oob || y_oob {
-                        match_found = false;
-                        break;
-                    } 
-
-                    let new_idx = new_x as usize + (new_y as usize)*board.cols;
-
-                    if board.es[new_idx] != word_chars[count as usize] {
-                        match_found = false;
-                        break;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    N, NE, E, SE, S, SW, W, NW,
+}
+
+impl Direction {
+    const ALL: [Direction; 8] = [
+        Direction::N, Direction::NE, Direction::E, Direction::SE,
+        Direction::S, Direction::SW, Direction::W, Direction::NW,
+    ];
+
+    /// Step as (dx, dy), with y growing downwards.
+    fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::N => (0, -1),
+            Direction::NE => (1, -1),
+            Direction::E => (1, 0),
+            Direction::SE => (1, 1),
+            Direction::S => (0, 1),
+            Direction::SW => (-1, 1),
+            Direction::W => (-1, 0),
+            Direction::NW => (-1, -1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WordMatch {
+    /// Index into the searched word list.
+    word: usize,
+    x: usize,
+    y: usize,
+    direction: Direction,
+}
+
+/// Prefix tree over the dictionary, so every direction from a cell is walked
+/// once no matter how many words there are.
+#[derive(Debug)]
+struct Trie {
+    // Few letters follow any prefix, so a list beats a map here.
+    children: Vec<Vec<(char, usize)>>,
+    // Words ending at each node. Duplicates in the dictionary share a node.
+    words: Vec<Vec<usize>>,
+}
+
+impl Trie {
+    fn new(words: &[&str]) -> Self {
+        let mut trie = Self { children: vec![Vec::new()], words: vec![Vec::new()] };
+        for (i, word) in words.iter().enumerate() {
+            let mut node = 0;
+            for c in word.chars() {
+                node = match trie.next(node, c) {
+                    Some(next) => next,
+                    None => {
+                        trie.children.push(Vec::new());
+                        trie.words.push(Vec::new());
+                        let next = trie.children.len() - 1;
+                        trie.children[node].push((c, next));
+                        next
+                    },
+                };
+            }
+            trie.words[node].push(i);
+        }
+        trie
+    }
+
+    fn next(&self, node: usize, c: char) -> Option<usize> {
+        self.children[node].iter().find(|&&(e, _)| e == c).map(|&(_, next)| next)
+    }
+}
+
+impl Board {
+    fn get(&self, x: i32, y: i32) -> Option<char> {
+        self.index(x, y).map(|i| self.es[i])
+    }
+
+    /// Finds every occurrence of the words in all eight directions. Words
+    /// that read the same both ways are found once per direction. On a
+    /// wrapping board a word longer than the board may cross cells twice.
+    fn find_words(&self, words: &[&str]) -> Vec<WordMatch> {
+        let trie = Trie::new(words);
+        let mut matches = Vec::new();
+
+        for i in 0..self.es.len() {
+            if trie.next(0, self.es[i]).is_none() { continue; }
+            let x = i % self.cols;
+            let y = i / self.cols;
+
+            for direction in Direction::ALL {
+                let (dx, dy) = direction.offset();
+                let (mut new_x, mut new_y) = (x as i32, y as i32);
+                let mut node = 0;
+
+                while let Some(c) = self.get(new_x, new_y) {
+                    node = match trie.next(node, c) {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    for &word in trie.words[node].iter() {
+                        matches.push(WordMatch { word, x, y, direction });
                     }
-                    count += 1;
+                    new_x += dx;
+                    new_y += dy;
                 }
-                if match_found { total_matches += 1; }
             }
         }
+        matches
+    }
+
+    /// The board with every letter that isn't part of a match blanked out.
+    fn highlight(&self, words: &[&str], matches: &[WordMatch]) -> String {
+        let mut keep = vec![false; self.es.len()];
+        for m in matches.iter() {
+            let (dx, dy) = m.direction.offset();
+            for j in 0..words[m.word].chars().count() as i32 {
+                let new_x = m.x as i32 + dx*j;
+                let new_y = m.y as i32 + dy*j;
+                keep[self.index(new_x, new_y).unwrap()] = true;
+            }
+        }
+
+        let mut s = String::new();
+        for (i, &e) in self.es.iter().enumerate() {
+            s.push(if keep[i] { e } else { '.' });
+            if (i + 1) % self.cols == 0 { s.push('\n'); }
+        }
+        s
     }
-    total_matches
 }
 
-fn part2(file_path: &str) -> i32 {
+fn matches_json(words: &[&str], matches: &[WordMatch]) -> String {
+    let matches: Vec<String> = matches.iter()
+        .map(|m| format!(
+            "{{\"word\":{},\"x\":{},\"y\":{},\"direction\":{}}}",
+            json_string(words[m.word]), m.x, m.y, json_string(&format!("{:?}", m.direction))
+        ))
+        .collect();
+    format!("[{}]", matches.join(","))
+}
+
+fn part1(file_path: &str) -> i32 {
     let board = Board::from_file(file_path); 
-    let mut total_matches: i32 = 0;
+    board.find_words(&["XMAS"]).len() as i32
+}
 
-    for i in 0..board.es.len() {
-        if board.es[i] != 'A' { continue; }
+/// A small grid of letters to look for on the board, where `.` matches any
+/// letter. Rows are separated by `/`, e.g. `M.S/.A./M.S` for an X-MAS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Template {
+    cells: Vec<Option<char>>,
+    rows: usize,
+    cols: usize,
+}
 
-        let x_pos = i % board.cols;
-        let y_pos = i / board.rows;
+impl Template {
+    fn parse(s: &str) -> Self {
+        let lines: Vec<&str> = s.split('/').collect();
+        let rows = lines.len();
+        let cols = lines[0].chars().count();
+        assert!(
+            lines.iter().all(|e| e.chars().count() == cols),
+            "Template rows differ in length: {}", s
+        );
 
-        let mut corner_chars = Vec::new();
-        
-        for x_offset in -1..=1 {
-            let mut has_broken = false;
-            for y_offset in -1..=1 {
-                if x_offset == 0 || y_offset == 0 { continue; }
+        let cells = lines.iter()
+            .flat_map(|e| e.chars())
+            .map(|c| if c == '.' { None } else { Some(c) })
+            .collect();
+        Self { cells, rows, cols }
+    }
 
-                let new_x: i32 = x_pos as i32 + x_offset;
-                let new_y: i32 = y_pos as i32 + y_offset;
+    /// Turns the template a quarter clockwise.
+    fn rotate(&self) -> Self {
+        let mut cells = vec![None; self.cells.len()];
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                cells[(self.rows - 1 - y) + x*self.rows] = self.cells[x + y*self.cols];
+            }
+        }
+        Self { cells, rows: self.cols, cols: self.rows }
+    }
 
-                let x_oob = new_x < 0 || new_x >= board.cols as i32;
-                let y_oob = new_y < 0 || new_y >= board.rows as i32;
+    /// Mirrors the template left to right.
+    fn reflect(&self) -> Self {
+        let mut cells = vec![None; self.cells.len()];
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                cells[(self.cols - 1 - x) + y*self.cols] = self.cells[x + y*self.cols];
+            }
+        }
+        Self { cells, rows: self.rows, cols: self.cols }
+    }
 
-                if x_oob || y_oob {
-                    has_broken = true;
-                    break;
-                } 
+    /// All distinct rotations and reflections, so that symmetric shapes
+    /// aren't counted more than once in the same spot.
+    fn variants(&self) -> Vec<Template> {
+        let mut variants: Vec<Template> = Vec::new();
+        let mut t = self.clone();
+        for _ in 0..4 {
+            for v in [t.clone(), t.reflect()] {
+                if !variants.contains(&v) { variants.push(v); }
+            }
+            t = t.rotate();
+        }
+        variants
+    }
+}
 
-                let new_idx = new_x as usize + (new_y as usize)*board.cols;
-                let new_char = board.es[new_idx];
+impl Board {
+    fn matches_at(&self, template: &Template, x: usize, y: usize) -> bool {
+        template.cells.iter().enumerate().all(|(j, cell)| match cell {
+            Some(c) => {
+                let new_x = (x + j % template.cols) as i32;
+                let new_y = (y + j / template.cols) as i32;
+                self.get(new_x, new_y) == Some(*c)
+            },
+            None => true,
+        })
+    }
 
-                if new_char != 'M' && new_char != 'S' {
-                    has_broken = true;
-                    break;
+    /// Counts the occurrences of the template under every rotation and
+    /// reflection.
+    fn count_template(&self, template: &Template) -> usize {
+        let mut total_matches = 0;
+        for variant in template.variants() {
+            for i in 0..self.es.len() {
+                if self.matches_at(&variant, i % self.cols, i / self.cols) {
+                    total_matches += 1;
                 }
-
-                corner_chars.push(new_char);
             }
-            if has_broken { break; }
-        }
-        if corner_chars.len() != 4 { continue; }
-        
-        // Corner chars offset in order: (-1,-1), (-1, 1), (1, -1), (1, 1)
-        // Opposite corners should not be the same.
-        if corner_chars[0] != corner_chars[3] && 
-            corner_chars[1] != corner_chars[2] {
-                total_matches += 1;
         }
+        total_matches
     }
-    total_matches
+}
+
+fn part2(file_path: &str) -> i32 {
+    let board = Board::from_file(file_path); 
+    board.count_template(&Template::parse("M.S/.A./M.S")) as i32
 }
 
 fn main() {
-    println!("Times XMAS appears: {}", part1("src/day04/input.txt"));
-    println!("Times X-MAS appears: {}", part2("src/day04/input.txt"));
+    let mut runner = Runner::new(4);
+    let input = runner.input().to_string();
+    runner.parse(|| Board::from_file(&input));
+    runner.part("Times XMAS appears", || part1(&input));
+    runner.part("Times X-MAS appears", || part2(&input));
+
+    if runner.flag("--words") {
+        let words: String = runner.param("words", "XMAS".to_string());
+        let words: Vec<&str> = words.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()).collect();
+        let board = Board::from_file(&input).with_edges(runner.param("edges", Edges::Bounded));
+        let matches = board.find_words(&words);
+        let mut text = String::new();
+        for m in matches.iter() {
+            text.push_str(&format!("{} at ({}, {}) going {:?}\n", words[m.word], m.x, m.y, m.direction));
+        }
+        text.push_str(&board.highlight(&words, &matches));
+        text.push_str(&format!("{} matches\n", matches.len()));
+        runner.section("words", &text, matches_json(&words, &matches));
+    }
+
+    if runner.flag("--template") {
+        let template: String = runner.param("template", "M.S/.A./M.S".to_string());
+        let board = Board::from_file(&input).with_edges(runner.param("edges", Edges::Bounded));
+        runner.part(&format!("Times {} appears", template), || {
+            board.count_template(&Template::parse(&template))
+        });
+    }
+    runner.finish();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     #[test]
     fn test_part1() {
         assert_eq!(18, part1("src/day04/test1.txt"));
     }
 
+    #[test]
+    fn test_find_words() {
+        let board = Board::from_file("src/day04/test1.txt");
+        let words = ["XMAS", "SAMX", "MAS", "SANTA"];
+        let matches = board.find_words(&words);
+
+        let count = |word: usize| matches.iter().filter(|m| m.word == word).count();
+        // Every XMAS is a SAMX read backwards.
+        assert_eq!(18, count(0));
+        assert_eq!(18, count(1));
+        assert_eq!(0, count(3));
+        assert!(matches.contains(&WordMatch { word: 0, x: 5, y: 0, direction: Direction::E }));
+        assert!(matches.contains(&WordMatch { word: 0, x: 4, y: 1, direction: Direction::W }));
+        assert!(matches.contains(&WordMatch { word: 2, x: 1, y: 0, direction: Direction::SE }));
+        assert_eq!(
+            "[{\"word\":\"XMAS\",\"x\":5,\"y\":0,\"direction\":\"E\"}]",
+            matches_json(&words, &[WordMatch { word: 0, x: 5, y: 0, direction: Direction::E }])
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let board = Board::from_file("src/day04/test1.txt");
+        let matches = board.find_words(&["XMAS"]);
+        let expected = "\
+            ....XXMAS.\n\
+            .SAMXMS...\n\
+            ...S..A...\n\
+            ..A.A.MS.X\n\
+            XMASAMX.MM\n\
+            X.....XA.A\n\
+            S.S.S.S.SS\n\
+            .A.A.A.A.A\n\
+            ..M.M.M.MM\n\
+            .X.X.XMASX\n";
+        assert_eq!(expected, board.highlight(&["XMAS"], &matches));
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(9, part2("src/day04/test2.txt"));
     }
+
+    #[test]
+    fn test_non_square() {
+        // test4.txt is test3.txt transposed, which keeps both counts.
+        for file_path in ["src/day04/test3.txt", "src/day04/test4.txt"] {
+            assert_eq!(6, part1(file_path));
+            assert_eq!(5, part2(file_path));
+        }
+
+        let wide = Board::from_file("src/day04/test3.txt");
+        assert_eq!((5, 10), (wide.rows, wide.cols));
+        assert_eq!(Some('A'), wide.get(9, 1));
+        assert_eq!(None, wide.get(0, 5));
+        assert_eq!(
+            fs::read_to_string("src/day04/test3.txt").unwrap().replace(|c: char| c != '\n', "."),
+            wide.highlight(&[], &[])
+        );
+
+        let tall = Board::from_file("src/day04/test4.txt");
+        assert_eq!((10, 5), (tall.rows, tall.cols));
+        assert!(tall.find_words(&["XMAS"]).contains(
+            &WordMatch { word: 0, x: 0, y: 5, direction: Direction::S }
+        ));
+    }
+
+    #[test]
+    fn test_edges() {
+        // One XMAS wraps around horizontally and one vertically.
+        let board = |edges| Board::from_file("src/day04/test5.txt").with_edges(edges);
+        let count = |edges| board(edges).find_words(&["XMAS"]).len();
+        assert_eq!(0, count(Edges::Bounded));
+        assert_eq!(1, count(Edges::WrapX));
+        assert_eq!(1, count(Edges::WrapY));
+        assert_eq!(2, count(Edges::Torus));
+
+        let torus = board(Edges::Torus);
+        assert_eq!(Some('X'), torus.get(-1, 0));
+        assert_eq!(Some('M'), torus.get(3, -1));
+        assert_eq!(torus.get(3, 2), torus.get(13, -6));
+        assert_eq!(None, board(Edges::WrapX).get(0, -1));
+        assert_eq!(None, board(Edges::WrapY).get(-1, 0));
+
+        let wrap_x = board(Edges::WrapX);
+        let matches = wrap_x.find_words(&["XMAS"]);
+        assert_eq!(vec![WordMatch { word: 0, x: 4, y: 0, direction: Direction::E }], matches);
+        assert_eq!("MAS.X\n.....\n.....\n.....\n", wrap_x.highlight(&["XMAS"], &matches));
+    }
+
+    #[test]
+    fn test_template_variants() {
+        let xmas = Template::parse("M.S/.A./M.S");
+        assert_eq!(4, xmas.variants().len());
+        assert!(xmas.variants().contains(&Template::parse("M.M/.A./S.S")));
+
+        let line = Template::parse("XMAS");
+        assert_eq!(4, line.variants().len());
+        assert!(line.variants().contains(&Template::parse("S/A/M/X")));
+
+        let l_shape = Template::parse("X./XX");
+        assert_eq!(4, l_shape.variants().len());
+        assert_eq!(8, Template::parse("X./XM").variants().len());
+    }
+
+    #[test]
+    fn test_templates() {
+        let board = Board::from_file("src/day04/test1.txt");
+        // Straight and diagonal lines together cover all eight directions.
+        let xmas = board.count_template(&Template::parse("XMAS"))
+            + board.count_template(&Template::parse("X.../.M../..A./...S"));
+        assert_eq!(18, xmas);
+        assert_eq!(9, board.count_template(&Template::parse("M.S/.A./M.S")));
+        let mas = board.count_template(&Template::parse("MAS"))
+            + board.count_template(&Template::parse("M../.A./..S"));
+        assert_eq!(board.find_words(&["MAS"]).len(), mas);
+        assert_eq!(0, board.count_template(&Template::parse("SS/SS")));
+    }
 }
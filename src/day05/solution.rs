@@ -1,6 +1,10 @@
 use std::fs::File;
 use std::io::Read;
-use std::collections::HashMap;
+use std::fmt;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use advent_of_code_2024::runner::{json_string, Runner};
+use advent_of_code_2024::warn;
 
 fn parse_input(file_path: &str) -> (Vec<(i32,i32)>, Vec<Vec<i32>>) {
     let mut input = String::new();
@@ -39,11 +43,11 @@ fn parse_input(file_path: &str) -> (Vec<(i32,i32)>, Vec<Vec<i32>>) {
 
 fn part1(file_path: &str) -> i32 {
     let (rules, updates) = parse_input(file_path);
-    let rule_map = generate_rule_map(&rules);
+    let rules = RuleSet::new(&rules);
     let mut total_sum: i32 = 0;
 
     for update in updates.iter() {
-        if follows_rules(update, &rule_map) {
+        if follows_rules(update, &rules) {
             let idx: usize = update.len() / 2;
             total_sum += update[idx] 
         }
@@ -63,31 +67,248 @@ fn generate_rule_map(rules: &Vec<(i32, i32)>) -> HashMap<i32, Vec<i32>> {
     rule_map
 }
 
-fn follows_rules(update: &Vec<i32>, rule_map: &HashMap<i32,Vec<i32>>) -> bool {
-    for i in 1..update.len() {
-        let rules = match rule_map.get(&update[i]) {
-            Some(list) => list,
-            None => continue,
-        };
+// Page numbers below this are stored in the bitset.
+const DENSE_PAGES: usize = 100;
+
+/// The rules indexed for constant-time lookups of whether one page has to
+/// come before another. Rules between page numbers below `DENSE_PAGES` are
+/// kept in a bit matrix, any rule with a larger page in a set of pairs.
+/// The pages each page has to come before are listed too, so sorting only
+/// visits the rules that apply.
+#[derive(Debug, Clone)]
+struct RuleSet {
+    /// Bit `a * DENSE_PAGES + b` is set for the rule `a|b`.
+    dense: Vec<u64>,
+    sparse: HashSet<(i32, i32)>,
+    after: HashMap<i32, Vec<i32>>,
+}
+
+impl RuleSet {
+    fn new(rules: &[(i32, i32)]) -> Self {
+        let mut dense = vec![0u64; (DENSE_PAGES * DENSE_PAGES).div_ceil(64)];
+        let mut sparse = HashSet::new();
+        let mut after: HashMap<i32, Vec<i32>> = HashMap::new();
+        for &(a, b) in rules.iter() {
+            let new = match Self::bit(a, b) {
+                Some(bit) => {
+                    let new = dense[bit / 64] & (1 << (bit % 64)) == 0;
+                    dense[bit / 64] |= 1 << (bit % 64);
+                    new
+                },
+                None => sparse.insert((a, b)),
+            };
+            if new { after.entry(a).or_default().push(b); }
+        }
+        Self { dense, sparse, after }
+    }
+
+    fn bit(a: i32, b: i32) -> Option<usize> {
+        let in_range = |page: i32| (0..DENSE_PAGES as i32).contains(&page);
+        if in_range(a) && in_range(b) {
+            Some(a as usize * DENSE_PAGES + b as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Whether there is a rule `a|b`.
+    fn before(&self, a: i32, b: i32) -> bool {
+        match Self::bit(a, b) {
+            Some(bit) => self.dense[bit / 64] & (1 << (bit % 64)) != 0,
+            None => self.sparse.contains(&(a, b)),
+        }
+    }
+
+    /// The pages `a` has to come before.
+    fn after(&self, a: i32) -> &[i32] {
+        self.after.get(&a).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Orders two pages by the rule between them. Pages without a rule
+    /// between them compare equal.
+    fn compare(&self, a: i32, b: i32) -> Ordering {
+        if self.before(a, b) {
+            Ordering::Less
+        } else if self.before(b, a) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
 
+fn follows_rules(update: &[i32], rules: &RuleSet) -> bool {
+    for i in 1..update.len() {
         for j in 0..i {
-            if rules.contains(&update[j]) {
+            if rules.before(update[i], update[j]) {
                 return false;
             }
         }
     }
-    return true;
+    true
+}
+
+/// A rule `a|b` broken by an update that has `b` before `a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Violation {
+    rule: (i32, i32),
+    /// Where `a` and `b` are in the update.
+    positions: (usize, usize),
+}
+
+fn find_violations(update: &[i32], rules: &RuleSet) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for i in 1..update.len() {
+        for j in 0..i {
+            if rules.before(update[i], update[j]) {
+                violations.push(Violation { rule: (update[i], update[j]), positions: (i, j) });
+            }
+        }
+    }
+    violations
+}
+
+/// How often each rule is broken across all updates, most broken first.
+fn most_broken(violations: &[Vec<Violation>]) -> Vec<((i32, i32), usize)> {
+    let mut counts: HashMap<(i32, i32), usize> = HashMap::new();
+    for v in violations.iter().flatten() {
+        *counts.entry(v.rule).or_default() += 1;
+    }
+    let mut counts: Vec<((i32, i32), usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|&(rule, count)| (Reverse(count), rule));
+    counts
+}
+
+fn format_violations(
+    updates: &[Vec<i32>],
+    violations: &[Vec<Violation>],
+    top: usize
+) -> String {
+    let mut s = String::new();
+    for (i, (update, found)) in updates.iter().zip(violations.iter()).enumerate() {
+        if found.is_empty() { continue; }
+        let pages: Vec<String> = update.iter().map(|e| e.to_string()).collect();
+        s.push_str(&format!("Update {} ({}):\n", i + 1, pages.join(",")));
+        for v in found.iter() {
+            s.push_str(&format!(
+                "    {}|{}: {} at {}, {} at {}\n",
+                v.rule.0, v.rule.1, v.rule.0, v.positions.0, v.rule.1, v.positions.1
+            ));
+        }
+    }
+
+    let invalid = violations.iter().filter(|e| !e.is_empty()).count();
+    s.push_str(&format!("{} of {} updates break a rule\n", invalid, updates.len()));
+    s.push_str("Most broken rules:\n");
+    for (rule, count) in most_broken(violations).into_iter().take(top) {
+        s.push_str(&format!("    {}|{}: {}\n", rule.0, rule.1, count));
+    }
+    s
+}
+
+fn violations_json(updates: &[Vec<i32>], violations: &[Vec<Violation>], top: usize) -> String {
+    let updates: Vec<String> = updates.iter().zip(violations.iter()).enumerate()
+        .map(|(i, (update, found))| {
+            let pages: Vec<String> = update.iter().map(|e| e.to_string()).collect();
+            let found: Vec<String> = found.iter()
+                .map(|v| format!(
+                    "{{\"rule\":[{},{}],\"positions\":[{},{}]}}",
+                    v.rule.0, v.rule.1, v.positions.0, v.positions.1
+                ))
+                .collect();
+            format!(
+                "{{\"update\":{},\"pages\":[{}],\"violations\":[{}]}}",
+                i + 1, pages.join(","), found.join(",")
+            )
+        })
+        .collect();
+    let rules: Vec<String> = most_broken(violations).into_iter().take(top)
+        .map(|(rule, count)| format!("{{\"rule\":[{},{}],\"count\":{}}}", rule.0, rule.1, count))
+        .collect();
+    format!("{{\"updates\":[{}],\"most_broken\":[{}]}}", updates.join(","), rules.join(","))
+}
+
+/// The rules as an adjacency list sorted by page, optionally restricted to
+/// the rules between the given pages. Pages without rules are included too.
+fn rule_graph(
+    rule_map: &HashMap<i32, Vec<i32>>,
+    pages: Option<&[i32]>
+) -> BTreeMap<i32, Vec<i32>> {
+    let keep = |page: &i32| pages.is_none_or(|pages| pages.contains(page));
+    let mut graph: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    if let Some(pages) = pages {
+        for &page in pages.iter() {
+            graph.entry(page).or_default();
+        }
+    }
+
+    for (page, after) in rule_map.iter().filter(|(page, _)| keep(page)) {
+        let mut after: Vec<i32> = after.iter().copied().filter(keep).collect();
+        after.sort();
+        for &e in after.iter() {
+            graph.entry(e).or_default();
+        }
+        graph.entry(*page).or_default().extend(after);
+    }
+    graph
+}
+
+/// The pages of the `update`th update, counting from 1 like the violation
+/// report, or `None` for 0 to export the full graph.
+fn graph_pages(updates: &[Vec<i32>], update: usize) -> Result<Option<&[i32]>, String> {
+    match update.checked_sub(1) {
+        None => Ok(None),
+        Some(i) => match updates.get(i) {
+            Some(pages) => Ok(Some(pages.as_slice())),
+            None => Err(format!("no update {}, there are {}", update, updates.len())),
+        },
+    }
+}
+
+fn format_adjacency_list(graph: &BTreeMap<i32, Vec<i32>>) -> String {
+    let mut s = String::new();
+    for (page, after) in graph.iter() {
+        s.push_str(&format!("{}:", page));
+        for e in after.iter() {
+            s.push_str(&format!(" {}", e));
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Graphviz source for the rule graph, with pages that appear in invalid
+/// updates filled red and the rest green.
+fn format_dot(graph: &BTreeMap<i32, Vec<i32>>, invalid_pages: &HashSet<i32>) -> String {
+    let mut s = String::from("digraph rules {\n    node [shape=circle, style=filled];\n");
+    for page in graph.keys() {
+        let colour = if invalid_pages.contains(page) { "#f4a6a6" } else { "#a6e3a6" };
+        s.push_str(&format!("    {} [fillcolor=\"{}\"];\n", page, colour));
+    }
+    for (page, after) in graph.iter() {
+        for e in after.iter() {
+            s.push_str(&format!("    {} -> {};\n", page, e));
+        }
+    }
+    s.push_str("}\n");
+    s
 }
 
 fn part2(file_path: &str) -> i32 {
-    let (rules, mut updates) = parse_input(file_path);
-    let rule_map = generate_rule_map(&rules);
+    let (rules, updates) = parse_input(file_path);
+    let rules = RuleSet::new(&rules);
     let mut total_sum: i32 = 0;
 
-    for update in updates.iter_mut() {
-        if !follows_rules(update, &rule_map) {
+    for update in updates.iter() {
+        if !follows_rules(update, &rules) {
             // Order the updates
-            order_update(update, &rule_map);
+            let update = match sort_update(update, &rules) {
+                Ok(sorted) => sorted,
+                Err(e) => {
+                    warn!("Skipping update {:?}: {}", update, e);
+                    continue;
+                },
+            };
 
             let idx: usize = update.len() / 2;
             total_sum += update[idx] 
@@ -96,30 +317,161 @@ fn part2(file_path: &str) -> i32 {
     total_sum
 }
 
-fn order_update(update: &mut Vec<i32>, rule_map: &HashMap<i32, Vec<i32>>) {
-    for i in 1..update.len() {
-        let rules = match rule_map.get(&update[i]) {
-            Some(list) => list,
-            None => continue,
-        };
-
-        let mut new_idx = i;
-        for j in (0..i).rev() {
-            if rules.contains(&update[j]) {
-                new_idx = j;
-            }
+/// Rules that contradict each other, e.g. `[1, 2, 3]` for 1|2, 2|3 and 3|1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CycleError {
+    cycle: Vec<i32>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pages: Vec<String> = self.cycle.iter()
+            .chain(self.cycle.first())
+            .map(|e| e.to_string())
+            .collect();
+        write!(f, "rules form a cycle: {}", pages.join(" -> "))
+    }
+}
+
+/// Orders the pages of an update by the rules that apply between them.
+///
+/// A plain sort with the comparator is all it takes when the rules totally
+/// order the pages, as they do in the puzzle input. Otherwise the comparator
+/// isn't a total order, which `sort_by` may panic on, so it falls back to
+/// Kahn's algorithm, where pages the rules don't order keep their relative
+/// order.
+fn sort_update(update: &[i32], rules: &RuleSet) -> Result<Vec<i32>, CycleError> {
+    let edges = update_edges(update, rules);
+    if !is_total_order(update, &edges, rules) {
+        return topological_sort(update, &edges);
+    }
+    let mut sorted = update.to_vec();
+    sorted.sort_by(|&a, &b| rules.compare(a, b));
+    Ok(sorted)
+}
+
+/// Edges between positions in the update, for the rules among its pages.
+/// Only the rules starting at one of the pages are looked at.
+fn update_edges(update: &[i32], rules: &RuleSet) -> Vec<Vec<usize>> {
+    let position: HashMap<i32, usize> = update.iter().enumerate()
+        .map(|(i, &page)| (page, i))
+        .collect();
+    update.iter()
+        .map(|&a| rules.after(a).iter().filter_map(|b| position.get(b).copied()).collect())
+        .collect()
+}
+
+/// Whether the rules order every pair of pages one way, without a cycle,
+/// so the comparator is consistent. With no pair ordered both ways, that
+/// holds exactly when the pages come before 0, 1, ..., n-1 of the others.
+fn is_total_order(update: &[i32], edges: &[Vec<usize>], rules: &RuleSet) -> bool {
+    let mut seen = vec![false; update.len()];
+    for next in edges.iter() {
+        if next.len() >= update.len() || seen[next.len()] { return false; }
+        seen[next.len()] = true;
+    }
+    edges.iter().enumerate()
+        .all(|(i, next)| next.iter().all(|&j| !rules.before(update[j], update[i])))
+}
+
+fn topological_sort(update: &[i32], edges: &[Vec<usize>]) -> Result<Vec<i32>, CycleError> {
+    let mut in_degree = vec![0usize; update.len()];
+    for &j in edges.iter().flatten() {
+        in_degree[j] += 1;
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..update.len())
+        .filter(|&i| in_degree[i] == 0)
+        .map(Reverse)
+        .collect();
+    let mut sorted = Vec::with_capacity(update.len());
+    while let Some(Reverse(i)) = ready.pop() {
+        sorted.push(update[i]);
+        for &j in edges[i].iter() {
+            in_degree[j] -= 1;
+            if in_degree[j] == 0 { ready.push(Reverse(j)); }
         }
-        if new_idx < i {
-            let temp = update.remove(i);
-            update.insert(new_idx, temp);
+    }
+
+    if sorted.len() < update.len() {
+        return Err(CycleError { cycle: find_cycle(update, edges, &in_degree) });
+    }
+    Ok(sorted)
+}
+
+/// Walks back along incoming edges among the pages Kahn's algorithm couldn't
+/// place. Every one of them has an unplaced predecessor, so the walk must
+/// eventually revisit a page, closing a cycle.
+fn find_cycle(update: &[i32], edges: &[Vec<usize>], in_degree: &[usize]) -> Vec<i32> {
+    let mut predecessor = vec![None; update.len()];
+    for (i, next) in edges.iter().enumerate() {
+        if in_degree[i] == 0 { continue; }
+        for &j in next.iter() {
+            predecessor[j] = Some(i);
         }
     }
-    assert!(follows_rules(update, rule_map));
+
+    let mut seen = vec![false; update.len()];
+    let mut i = (0..update.len()).find(|&i| in_degree[i] > 0).unwrap();
+    while !seen[i] {
+        seen[i] = true;
+        i = predecessor[i].unwrap();
+    }
+
+    let mut cycle = vec![update[i]];
+    let mut j = predecessor[i].unwrap();
+    while j != i {
+        cycle.push(update[j]);
+        j = predecessor[j].unwrap();
+    }
+    cycle.reverse();
+    cycle
 }
 
 fn main() {
-    println!("Sum of valid updates: {}", part1("src/day05/input.txt"));
-    println!("Sum of invalid updates (fixed): {}", part2("src/day05/input.txt"));
+    let mut runner = Runner::new(5);
+    let input = runner.input().to_string();
+    runner.parse(|| parse_input(&input));
+    runner.part("Sum of valid updates", || part1(&input));
+    runner.part("Sum of invalid updates (fixed)", || part2(&input));
+
+    if runner.flag("--violations") {
+        let (rules, updates) = parse_input(&input);
+        let rules = RuleSet::new(&rules);
+        let violations: Vec<Vec<Violation>> = updates.iter()
+            .map(|e| find_violations(e, &rules))
+            .collect();
+        let top = runner.param("top", 10);
+        runner.section(
+            "violations",
+            &format_violations(&updates, &violations, top),
+            violations_json(&updates, &violations, top)
+        );
+    }
+
+    if runner.flag("--graph") {
+        let (rules, updates) = parse_input(&input);
+        let rule_map = generate_rule_map(&rules);
+        match graph_pages(&updates, runner.param("graph_update", 0)) {
+            Ok(pages) => {
+                let graph = rule_graph(&rule_map, pages);
+                let text = if runner.param("graph_format", "dot".to_string()) == "adjacency" {
+                    format_adjacency_list(&graph)
+                } else {
+                    let rules = RuleSet::new(&rules);
+                    let invalid_pages: HashSet<i32> = updates.iter()
+                        .filter(|e| !follows_rules(e, &rules))
+                        .flatten()
+                        .copied()
+                        .collect();
+                    format_dot(&graph, &invalid_pages)
+                };
+                runner.section("graph", &text, json_string(&text));
+            },
+            Err(e) => warn!("Not exporting the rule graph: {}", e),
+        }
+    }
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -135,4 +487,200 @@ mod tests {
     fn test_part2() {
         assert_eq!(123, part2("src/day05/test.txt")) 
     }
+
+    #[test]
+    fn test_violations() {
+        let (rules, updates) = parse_input("src/day05/test.txt");
+        let rules = RuleSet::new(&rules);
+        let violations: Vec<Vec<Violation>> = updates.iter()
+            .map(|e| find_violations(e, &rules))
+            .collect();
+
+        for (update, found) in updates.iter().zip(violations.iter()) {
+            assert_eq!(follows_rules(update, &rules), found.is_empty());
+        }
+        assert_eq!(vec![Violation { rule: (97, 75), positions: (1, 0) }], violations[3]);
+        assert_eq!(4, violations[5].len());
+        assert_eq!(vec![((29, 13), 2), ((47, 13), 1)], most_broken(&violations)[..2]);
+
+        let report = format_violations(&updates, &violations, 2);
+        assert!(report.starts_with("Update 4 (75,97,47,61,53):\n    97|75: 97 at 1, 75 at 0\n"));
+        assert!(report.ends_with(
+            "3 of 6 updates break a rule\nMost broken rules:\n    29|13: 2\n    47|13: 1\n"
+        ));
+
+        let json = violations_json(&updates[3..4], &violations[3..4], 1);
+        assert_eq!(
+            "{\"updates\":[{\"update\":1,\"pages\":[75,97,47,61,53],\
+             \"violations\":[{\"rule\":[97,75],\"positions\":[1,0]}]}],\
+             \"most_broken\":[{\"rule\":[97,75],\"count\":1}]}",
+            json
+        );
+    }
+
+    #[test]
+    fn test_rule_graph() {
+        let (rules, _) = parse_input("src/day05/test.txt");
+        let rule_map = generate_rule_map(&rules);
+
+        let graph = rule_graph(&rule_map, None);
+        assert_eq!(7, graph.len());
+        assert_eq!(vec![13, 29, 47, 53, 61, 75], graph[&97]);
+        assert!(graph[&13].is_empty());
+
+        let graph = rule_graph(&rule_map, Some(&[61, 13, 29, 5]));
+        assert_eq!("5:\n13:\n29: 13\n61: 13 29\n", format_adjacency_list(&graph));
+
+        let (_, updates) = parse_input("src/day05/test.txt");
+        assert_eq!(Ok(None), graph_pages(&updates, 0));
+        assert_eq!(Ok(Some(&[75, 29, 13][..])), graph_pages(&updates, 3));
+        assert_eq!(Err("no update 7, there are 6".to_string()), graph_pages(&updates, 7));
+
+        let invalid_pages: HashSet<i32> = [61, 13, 29].into_iter().collect();
+        let graph = rule_graph(&rule_map, Some(&[29, 13]));
+        assert_eq!(
+            "digraph rules {\n    node [shape=circle, style=filled];\n\
+             \x20   13 [fillcolor=\"#f4a6a6\"];\n\
+             \x20   29 [fillcolor=\"#f4a6a6\"];\n\
+             \x20   29 -> 13;\n}\n",
+            format_dot(&graph, &invalid_pages)
+        );
+    }
+
+    #[test]
+    fn test_sort_update() {
+        let (rules, _) = parse_input("src/day05/test.txt");
+        let rules = RuleSet::new(&rules);
+        assert_eq!(Ok(vec![97, 75, 47, 61, 53]), sort_update(&[75, 97, 47, 61, 53], &rules));
+        assert_eq!(Ok(vec![61, 29, 13]), sort_update(&[61, 13, 29], &rules));
+        assert_eq!(Ok(vec![97, 75, 47, 29, 13]), sort_update(&[97, 13, 75, 29, 47], &rules));
+        // Otherwise pages keep their order, so 13 only moves behind 75.
+        let update = [5, 13, 1, 75];
+        assert_eq!(Ok(vec![5, 1, 75, 13]), topological_sort(&update, &update_edges(&update, &rules)));
+    }
+
+    #[test]
+    fn test_rule_set() {
+        let (rules, updates) = parse_input("src/day05/test.txt");
+        let dense = RuleSet::new(&rules);
+        assert!(dense.sparse.is_empty());
+
+        // The same rules with pages shifted past the bitset.
+        let shift = |e: i32| e + 1000;
+        let shifted: Vec<(i32, i32)> = rules.iter().map(|&(a, b)| (shift(a), shift(b))).collect();
+        let sparse = RuleSet::new(&shifted);
+        assert_eq!(rules.len(), sparse.sparse.len());
+
+        // Only the rules with a large page go in the set.
+        let mixed = RuleSet::new(&[(1, 2), (99, 0), (99, 100), (-1, 5)]);
+        assert_eq!(2, mixed.sparse.len());
+        assert!(mixed.before(1, 2) && mixed.before(99, 0));
+        assert!(mixed.before(99, 100) && mixed.before(-1, 5));
+        assert!(!mixed.before(2, 1) && !mixed.before(100, 99));
+        assert_eq!(&[0, 100], mixed.after(99));
+        assert!(mixed.after(2).is_empty());
+
+        // Repeated rules are listed once.
+        let repeated = RuleSet::new(&[(1, 2), (1, 2), (200, 3), (200, 3)]);
+        assert_eq!((&[2][..], &[3][..]), (repeated.after(1), repeated.after(200)));
+
+        assert!(dense.before(47, 53) && !dense.before(53, 47));
+        assert_eq!(Ordering::Less, dense.compare(97, 13));
+        assert_eq!(Ordering::Greater, dense.compare(13, 97));
+        assert_eq!(Ordering::Equal, dense.compare(13, 13));
+        assert!(!dense.before(-1, 13) && !dense.before(47, 100));
+
+        for update in updates.iter() {
+            let shifted_update: Vec<i32> = update.iter().copied().map(shift).collect();
+            assert_eq!(follows_rules(update, &dense), follows_rules(&shifted_update, &sparse));
+            assert_eq!(
+                sort_update(update, &dense).unwrap().into_iter().map(shift).collect::<Vec<i32>>(),
+                sort_update(&shifted_update, &sparse).unwrap()
+            );
+        }
+    }
+
+    // Deterministic xorshift, so the large tests don't need a dependency.
+    fn shuffled(pages: &[i32], seed: &mut u64) -> Vec<i32> {
+        let mut pages = pages.to_vec();
+        for i in (1..pages.len()).rev() {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            pages.swap(i, (*seed % (i as u64 + 1)) as usize);
+        }
+        pages
+    }
+
+    #[test]
+    fn test_sort_large_updates() {
+        let pages: Vec<i32> = (10..40).collect();
+        let mut seed = 0x2024;
+
+        // Every pair ordered takes the comparator path.
+        let total: Vec<(i32, i32)> = pages.iter()
+            .flat_map(|&a| pages.iter().filter(move |&&b| a < b).map(move |&b| (a, b)))
+            .collect();
+        let rules = RuleSet::new(&total);
+        for _ in 0..50 {
+            let update = shuffled(&pages, &mut seed);
+            assert!(is_total_order(&update, &update_edges(&update, &rules), &rules));
+            assert_eq!(Ok(pages.clone()), sort_update(&update, &rules));
+        }
+
+        // Only even pages are ordered, the odd ones are free to go anywhere.
+        let partial: Vec<(i32, i32)> = total.iter().copied()
+            .filter(|&(a, b)| a % 2 == 0 && b % 2 == 0)
+            .collect();
+        let rules = RuleSet::new(&partial);
+        for _ in 0..50 {
+            let update = shuffled(&pages, &mut seed);
+            assert!(!is_total_order(&update, &update_edges(&update, &rules), &rules));
+            let sorted = sort_update(&update, &rules).unwrap();
+            assert!(follows_rules(&sorted, &rules));
+
+            let odd = |v: &[i32]| -> Vec<i32> { v.iter().copied().filter(|e| e % 2 == 1).collect() };
+            assert_eq!(odd(&update), odd(&sorted));
+            let mut all = sorted.clone();
+            all.sort();
+            assert_eq!(pages, all);
+        }
+    }
+
+    #[test]
+    fn test_large_cycle() {
+        let pages: Vec<i32> = (1..=30).collect();
+        let mut seed = 0x5eed;
+
+        // A chain 1|2|...|30 closed by 30|1, on top of a complete order.
+        let mut rules: Vec<(i32, i32)> = pages.iter()
+            .flat_map(|&a| pages.iter().filter(move |&&b| a < b).map(move |&b| (a, b)))
+            .filter(|&pair| pair != (1, 30))
+            .collect();
+        rules.push((30, 1));
+        let rules = RuleSet::new(&rules);
+
+        for _ in 0..50 {
+            let update = shuffled(&pages, &mut seed);
+            assert!(!is_total_order(&update, &update_edges(&update, &rules), &rules));
+            let err = sort_update(&update, &rules).unwrap_err();
+            // Every rule in the reported cycle exists.
+            let n = err.cycle.len();
+            assert!(n >= 2);
+            for i in 0..n {
+                assert!(rules.before(err.cycle[i], err.cycle[(i + 1) % n]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cycle() {
+        let rules = RuleSet::new(&[(1, 2), (2, 3), (3, 4), (4, 2), (5, 1)]);
+        let err = sort_update(&[4, 5, 3, 1, 2], &rules).unwrap_err();
+        assert_eq!(vec![2, 3, 4], err.cycle);
+        assert_eq!("rules form a cycle: 2 -> 3 -> 4 -> 2", err.to_string());
+
+        // The cycle only matters for updates containing all of its pages.
+        assert_eq!(Ok(vec![5, 1, 2, 3]), sort_update(&[3, 2, 1, 5], &rules));
+    }
 }
@@ -1,7 +1,8 @@
 use std::fs::File;
 use std::io::Read;
-use std::collections::{HashSet, HashMap};
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+use advent_of_code_2024::runner::{json_string, Runner};
 
 #[derive(Default, Debug)]
 struct Map {
@@ -82,79 +83,332 @@ fn get_visited_squares(map: &mut Map) -> HashSet<(usize, usize)> {
     visited
 }
 
+// Directions in the order the guard turns through them.
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn dir_index(dir: (i32, i32)) -> usize {
+    DIRECTIONS.iter().position(|&e| e == dir).unwrap()
+}
+
+/// For every square and direction, the square the guard stops at in front of
+/// the next obstruction, or `None` if she walks off the map. Lets the guard
+/// jump straight from one turn to the next.
+#[derive(Debug)]
+struct JumpTable {
+    width: usize,
+    jumps: Vec<[Option<(usize, usize)>; 4]>,
+}
+
+impl JumpTable {
+    fn new(map: &Map) -> Self {
+        let (width, height) = (map.width, map.height);
+        let mut jumps = vec![[None; 4]; width * height];
+
+        // Sweep every row and column against each direction, remembering the
+        // square in front of the last obstruction seen.
+        for (d, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+            let lines = if dx == 0 { width } else { height };
+            let len = if dx == 0 { height } else { width };
+            for line in 0..lines {
+                let mut stop = None;
+                for k in 0..len {
+                    // Walk against the direction, so from the far edge.
+                    let k = if dx + dy < 0 { k } else { len - 1 - k };
+                    let pos = if dx == 0 { (line, k) } else { (k, line) };
+                    if map.obstructions.contains(&pos) {
+                        let x = pos.0 as i32 - dx;
+                        let y = pos.1 as i32 - dy;
+                        stop = if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                            None
+                        } else {
+                            Some((x as usize, y as usize))
+                        };
+                    } else {
+                        jumps[pos.0 + pos.1*width][d] = stop;
+                    }
+                }
+            }
+        }
+        Self { width, jumps }
+    }
+
+    /// Where the guard stops walking from `pos` in direction `d`, with an
+    /// extra obstruction placed at `extra`. Instead of patching the table the
+    /// jump is cut short when the extra obstruction lies in the way, which
+    /// keeps the table shareable between threads.
+    fn jump(&self, pos: (usize, usize), d: usize, extra: (usize, usize)) -> Option<(usize, usize)> {
+        let stop = self.jumps[pos.0 + pos.1*self.width][d];
+        let (dx, dy) = DIRECTIONS[d];
+
+        // Steps from pos to a square ahead of it, if the square is ahead.
+        let steps = |target: (usize, usize)| -> Option<i32> {
+            let (x, y) = (target.0 as i32 - pos.0 as i32, target.1 as i32 - pos.1 as i32);
+            let n = x*dx + y*dy;
+            if n > 0 && (x, y) == (dx*n, dy*n) { Some(n) } else { None }
+        };
+
+        let Some(n) = steps(extra) else { return stop; };
+        let blocks = match stop {
+            Some(stop) => steps(stop).unwrap_or(0) >= n,
+            None => true,
+        };
+        if blocks {
+            Some(((pos.0 as i32 + dx*(n - 1)) as usize, (pos.1 as i32 + dy*(n - 1)) as usize))
+        } else {
+            stop
+        }
+    }
+}
+
 fn part2(file_path: &str) -> i32 {
-    // Still kinda slow...
-    let mut map = Map::from_file(file_path);
-    let original_pos = map.guard_pos;
-    let original_dir = map.guard_dir;
-    let candidates = get_visited_squares(&mut map);
-    let mut total_looping_obstructions = 0;
+    let map = Map::from_file(file_path);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    count_looping_obstructions(&map, threads) as i32
+}
+
+/// Squares where an obstruction changes the guard's route: the ones she
+/// visits, except where she starts.
+fn candidates(map: &Map) -> Vec<(usize, usize)> {
+    let mut walk = Map { obstructions: map.obstructions.clone(), ..*map };
+    let mut candidates: Vec<(usize, usize)> = get_visited_squares(&mut walk).into_iter()
+        .filter(|&e| e != map.guard_pos)
+        .collect();
+    candidates.sort_by_key(|&(x, y)| (y, x));
+    candidates
+}
+
+/// Tries an obstruction on every square of the guard's route except the
+/// start, spreading the candidates over `threads` threads.
+fn count_looping_obstructions(map: &Map, threads: usize) -> usize {
+    let table = JumpTable::new(map);
+    let candidates = candidates(map);
 
-    for &square in candidates.iter() {
-        if square == original_pos{ continue; }
+    let chunk_size = candidates.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = candidates.chunks(chunk_size)
+            .map(|chunk| {
+                let table = &table;
+                scope.spawn(move || {
+                    chunk.iter().filter(|&&e| contains_loop(map, table, e)).count()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|e| e.join().unwrap()).sum()
+    })
+}
 
-        map.guard_pos = original_pos;
-        map.guard_dir = original_dir;
+fn contains_loop(map: &Map, table: &JumpTable, obstruction: (usize, usize)) -> bool {
+    // Recording positions where a turn has been made, one bit per direction.
+    let mut turns: Vec<u8> = vec![0; map.width * map.height];
+    let mut pos = map.guard_pos;
+    let mut d = dir_index(map.guard_dir);
 
-        map.obstructions.insert(square);
+    loop {
+        pos = match table.jump(pos, d, obstruction) {
+            Some(next) => next,
+            None => return false,
+        };
 
-        if contains_loop(&mut map) {
-            total_looping_obstructions += 1;
+        let seen = &mut turns[pos.0 + pos.1*map.width];
+        if *seen & (1 << d) != 0 {
+            return true;
         }
-        map.obstructions.remove(&square);
+        *seen |= 1 << d;
+        d = (d + 1) % 4;
+    }
+}
+
+type GuardState = ((usize, usize), (i32, i32));
+
+#[derive(Debug)]
+struct Trace {
+    /// Every position and direction of the guard in order, including a state
+    /// for each turn on the spot.
+    states: Vec<GuardState>,
+    /// Index of the state her loop starts at, `None` if she leaves the map.
+    loop_start: Option<usize>,
+}
+
+impl Trace {
+    fn cycle(&self) -> Option<&[GuardState]> {
+        self.loop_start.map(|i| &self.states[i..])
     }
-    total_looping_obstructions
 }
 
-fn contains_loop(map: &mut Map) -> bool {
-    // Recording positions where a turn has been made and the guard's direction.
-    let mut snapshots: HashMap<(usize, usize), Vec<(i32, i32)>> = HashMap::new();
+/// Walks the guard square by square, with an optional extra obstruction,
+/// until she leaves the map or gets back to a state she has been in.
+fn trace_path(map: &Map, obstruction: Option<(usize, usize)>) -> Trace {
+    let mut seen: HashMap<GuardState, usize> = HashMap::new();
+    let mut states = Vec::new();
+    let mut state = (map.guard_pos, map.guard_dir);
 
     loop {
-        let new_x = map.guard_pos.0 as i32 + map.guard_dir.0;
-        let new_y = map.guard_pos.1 as i32 + map.guard_dir.1;
-        
+        if let Some(&i) = seen.get(&state) {
+            return Trace { states, loop_start: Some(i) };
+        }
+        seen.insert(state, states.len());
+        states.push(state);
+
+        let ((x, y), dir) = state;
+        let new_x = x as i32 + dir.0;
+        let new_y = y as i32 + dir.1;
+
         let x_oob = new_x < 0 || new_x >= map.width as i32;
         let y_oob = new_y < 0 || new_y >= map.height as i32;
 
-        if x_oob || y_oob { 
-            return false;
-        } 
-
-        if map.obstructions.contains(&(new_x as usize, new_y as usize)) {
-            if let Some(list) = snapshots.get_mut(&map.guard_pos) {
-                if list.contains(&map.guard_dir) {
-                    return true;
-                } else {
-                    list.push(map.guard_dir);
-                }
-            } else {
-                snapshots.insert(map.guard_pos, vec![map.guard_dir]);
-            }
+        if x_oob || y_oob {
+            return Trace { states, loop_start: None };
+        }
 
-            let new_x_dir = -map.guard_dir.1;
-            let new_y_dir = map.guard_dir.0;
-            map.guard_dir = (new_x_dir, new_y_dir);
+        let new_pos = (new_x as usize, new_y as usize);
+        if map.obstructions.contains(&new_pos) || obstruction == Some(new_pos) {
+            state = ((x, y), (-dir.1, dir.0));
         } else {
-            map.guard_pos = (new_x as usize, new_y as usize);
+            state = (new_pos, dir);
         }
     }
 }
 
+/// Every obstruction that traps the guard, along with the route that gets
+/// her stuck.
+fn loop_witnesses(map: &Map) -> Vec<((usize, usize), Trace)> {
+    let table = JumpTable::new(map);
+    candidates(map).into_iter()
+        .filter(|&e| contains_loop(map, &table, e))
+        .map(|e| (e, trace_path(map, Some(e))))
+        .collect()
+}
+
+fn dir_char(dir: (i32, i32)) -> char {
+    match dir {
+        (0, -1) => '^',
+        (1, 0) => '>',
+        (0, 1) => 'v',
+        (-1, 0) => '<',
+        _ => unreachable!(),
+    }
+}
+
+fn format_coords(states: &[GuardState]) -> String {
+    let mut s = String::new();
+    for &((x, y), dir) in states.iter() {
+        s.push_str(&format!("{},{},{}\n", x, y, dir_char(dir)));
+    }
+    s
+}
+
+fn state_json(&((x, y), dir): &GuardState) -> String {
+    format!("{{\"x\":{},\"y\":{},\"dir\":{}}}", x, y, json_string(&dir_char(dir).to_string()))
+}
+
+fn coords_json(states: &[GuardState]) -> String {
+    let states: Vec<String> = states.iter().map(state_json).collect();
+    format!("[{}]", states.join(","))
+}
+
+/// Each witness as its obstruction, the length of the loop and the state
+/// the guard enters it in.
+fn witnesses_json(witnesses: &[((usize, usize), Trace)]) -> String {
+    let witnesses: Vec<String> = witnesses.iter()
+        .map(|((x, y), trace)| {
+            let cycle = trace.cycle().unwrap();
+            format!(
+                "{{\"obstruction\":[{},{}],\"length\":{},\"start\":{}}}",
+                x, y, cycle.len(), state_json(&cycle[0])
+            )
+        })
+        .collect();
+    format!("[{}]", witnesses.join(","))
+}
+
+/// Draws the states on the map, `|` and `-` for squares walked up and down or
+/// sideways and `+` where the guard went both ways. An extra obstruction is
+/// drawn as `O`.
+fn render_path(map: &Map, states: &[GuardState], obstruction: Option<(usize, usize)>) -> String {
+    // Bit 0 for moving vertically, bit 1 for horizontally.
+    let mut walked: HashMap<(usize, usize), u8> = HashMap::new();
+    for &(pos, dir) in states.iter() {
+        *walked.entry(pos).or_default() |= if dir.0 == 0 { 1 } else { 2 };
+    }
+
+    let mut s = String::new();
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let c = if map.obstructions.contains(&(x, y)) {
+                '#'
+            } else if obstruction == Some((x, y)) {
+                'O'
+            } else if (x, y) == map.guard_pos {
+                dir_char(map.guard_dir)
+            } else {
+                match walked.get(&(x, y)) {
+                    Some(1) => '|',
+                    Some(2) => '-',
+                    Some(_) => '+',
+                    None => '.',
+                }
+            };
+            s.push(c);
+        }
+        s.push('\n');
+    }
+    s
+}
+
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Total squares visited: {} (Duration: {:.2?})",
-        part1("src/day06/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(6);
+    let input = runner.input().to_string();
+    runner.parse(|| Map::from_file(&input));
+    runner.part("Total squares visited", || part1(&input));
+    runner.part("Possible looping obstructions", || part2(&input));
+
+    let coords = runner.param("trace_format", "map".to_string()) == "coords";
+    let format_states = |map: &Map, states: &[GuardState], obstruction| {
+        if coords {
+            format_coords(states)
+        } else {
+            render_path(map, states, obstruction)
+        }
+    };
+
+    if runner.flag("--trace") {
+        let map = Map::from_file(&input);
+        let trace = trace_path(&map, None);
+        let text = format_states(&map, &trace.states, None);
+        runner.section("trace", &text, coords_json(&trace.states));
+    }
 
-    start = Instant::now();
-    println!(
-        "Possible looping obstructions: {} (Duration: {:.2?})",
-        part2("src/day06/input.txt"),
-        start.elapsed()
-    );
+    if runner.flag("--loops") {
+        let map = Map::from_file(&input);
+        let witnesses = loop_witnesses(&map);
+        let mut text = String::new();
+        for (obstruction, trace) in witnesses.iter() {
+            let cycle = trace.cycle().unwrap();
+            let ((x, y), dir) = cycle[0];
+            text.push_str(&format!(
+                "Obstruction at {:?}: loop of {} states from ({}, {}) going {}\n",
+                obstruction, cycle.len(), x, y, dir_char(dir)
+            ));
+        }
+        runner.section("loops", &text, witnesses_json(&witnesses));
+
+        // 1-based index into the list above, 0 for none.
+        let witness: usize = runner.param("witness", 0);
+        if let Some((obstruction, trace)) = witness.checked_sub(1).and_then(|i| witnesses.get(i)) {
+            let cycle = trace.cycle().unwrap();
+            let text = format_states(&map, cycle, Some(*obstruction));
+            runner.section("witness", &text, coords_json(cycle));
+        }
+    }
+
+    if runner.flag("--threads") {
+        let map = Map::from_file(&input);
+        let threads = runner.param("threads", 1);
+        runner.part(&format!("Possible looping obstructions ({} threads)", threads), || {
+            count_looping_obstructions(&map, threads)
+        });
+    }
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -170,4 +424,93 @@ mod tests {
     fn test_part2() {
        assert_eq!(6, part2("src/day06/test.txt"));
     }
+
+    #[test]
+    fn test_trace_path() {
+        let map = Map::from_file("src/day06/test.txt");
+
+        let trace = trace_path(&map, None);
+        assert_eq!(None, trace.loop_start);
+        let visited: HashSet<(usize, usize)> = trace.states.iter().map(|&(pos, _)| pos).collect();
+        assert_eq!(41, visited.len());
+        assert_eq!("4,6,^\n4,5,^\n", &format_coords(&trace.states)[..12]);
+        assert_eq!(((7, 9), (0, 1)), *trace.states.last().unwrap());
+
+        let trace = trace_path(&map, Some((6, 7)));
+        // She first walks into the loop on her way left along row 6.
+        assert_eq!(((6, 6), (-1, 0)), trace.cycle().unwrap()[0]);
+        let expected = "\
+            ....#.....\n\
+            ....+---+#\n\
+            ....|...|.\n\
+            ..#.|...|.\n\
+            ..+-+-+#|.\n\
+            ..|.|.|.|.\n\
+            .#+-^-+-+.\n\
+            ......O.#.\n\
+            #.........\n\
+            ......#...\n";
+        assert_eq!(expected, render_path(&map, &trace.states, Some((6, 7))));
+    }
+
+    #[test]
+    fn test_loop_witnesses() {
+        let map = Map::from_file("src/day06/test.txt");
+        let witnesses = loop_witnesses(&map);
+        let obstructions: Vec<(usize, usize)> = witnesses.iter().map(|(e, _)| *e).collect();
+        assert_eq!(vec![(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)], obstructions);
+
+        let (obstruction, trace) = &witnesses[0];
+        let expected = "\
+            ....#.....\n\
+            ....+---+#\n\
+            ....|...|.\n\
+            ..#.|...|.\n\
+            ....|..#|.\n\
+            ....|...|.\n\
+            .#.O^---+.\n\
+            ........#.\n\
+            #.........\n\
+            ......#...\n";
+        assert_eq!(expected, render_path(&map, trace.cycle().unwrap(), Some(*obstruction)));
+        assert_eq!(
+            "[{\"obstruction\":[3,6],\"length\":22,\"start\":{\"x\":4,\"y\":6,\"dir\":\"^\"}}]",
+            witnesses_json(&witnesses[..1])
+        );
+        assert_eq!(
+            "[{\"x\":4,\"y\":6,\"dir\":\"^\"},{\"x\":4,\"y\":5,\"dir\":\"^\"}]",
+            coords_json(&trace.cycle().unwrap()[..2])
+        );
+    }
+
+    #[test]
+    fn test_jump_table() {
+        let map = Map::from_file("src/day06/test.txt");
+        let table = JumpTable::new(&map);
+        // Nowhere near the guard, so it doesn't get in the way.
+        let none = (0, 9);
+
+        assert_eq!(Some((4, 1)), table.jump((4, 6), 0, none));
+        assert_eq!(Some((8, 1)), table.jump((4, 1), 1, none));
+        assert_eq!(Some((8, 6)), table.jump((8, 1), 2, none));
+        assert_eq!(None, table.jump((7, 7), 2, none));
+        assert_eq!(Some((2, 6)), table.jump((9, 6), 3, none));
+        // Standing right in front of an obstruction.
+        assert_eq!(Some((4, 1)), table.jump((4, 1), 0, none));
+
+        // An extra obstruction only matters if it comes first.
+        assert_eq!(Some((4, 4)), table.jump((4, 6), 0, (4, 3)));
+        assert_eq!(Some((4, 1)), table.jump((4, 6), 0, (4, 0)));
+        assert_eq!(Some((4, 1)), table.jump((4, 6), 0, (3, 3)));
+        assert_eq!(Some((7, 5)), table.jump((7, 7), 0, (7, 2)));
+        assert_eq!(Some((7, 8)), table.jump((7, 7), 2, (7, 9)));
+    }
+
+    #[test]
+    fn test_threads() {
+        let map = Map::from_file("src/day06/test.txt");
+        for threads in [1, 2, 3, 64] {
+            assert_eq!(6, count_looping_obstructions(&map, threads));
+        }
+    }
 }
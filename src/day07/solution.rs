@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::Read;
-use std::time::Instant;
+use advent_of_code_2024::runner::Runner;
 
 fn parse_input(file_path: &str) -> Vec<(u64, Vec<u64>)>{
     let mut input = String::new();
@@ -105,19 +105,12 @@ fn number_of_digits(n: u64) -> u32 {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Total calibration result: {} (Duration: {:.2?})", 
-        part1("src/day07/input.txt"), 
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Total calibration result w/ concat: {} (Duration: {:.2?})",
-        part2("src/day07/input.txt"),
-        start.elapsed() 
-    );
+    let mut runner = Runner::new(7);
+    let input = runner.input().to_string();
+    runner.parse(|| parse_input(&input));
+    runner.part("Total calibration result", || part1(&input));
+    runner.part("Total calibration result w/ concat", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
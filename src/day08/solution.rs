@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::Read;
 use std::collections::{HashMap, HashSet};
-use std::time::Instant;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Default, Debug)]
 struct Map {
@@ -133,19 +133,12 @@ fn get_inline_antinodes(locations: &Vec<(usize, usize)>, map: &Map) -> HashSet<(
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Total antinode locations: {} (Duration: {:.2?})",
-        part1("src/day08/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Total antinode locations (new method): {} (Duration: {:.2?})",
-        part2("src/day08/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(8);
+    let input = runner.input().to_string();
+    runner.parse(|| Map::from_file(&input));
+    runner.part("Total antinode locations", || part1(&input));
+    runner.part("Total antinode locations (new method)", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
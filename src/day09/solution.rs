@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::Read;
-use std::time::Instant;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug, Default)]
 struct Disk {
@@ -141,19 +141,12 @@ fn part2(file_path: &str) -> i64 {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Resulting filesystem checksum: {} (Duration: {:.2?})",
-        part1("src/day09/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Resulting filesystem checksum (chunked): {} (Duration: {:.2?})",
-        part2("src/day09/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(9);
+    let input = runner.input().to_string();
+    runner.parse(|| Disk::from_file(&input));
+    runner.part("Resulting filesystem checksum", || part1(&input));
+    runner.part("Resulting filesystem checksum (chunked)", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use std::io::Read;
 use std::fs::File;
-use std::time::Instant;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug, Default)]
 struct TopographicMap {
@@ -137,19 +137,12 @@ fn part2(file_path: &str) -> i32 {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Total score of trailheads: {} (Duration: {:.2?})",
-        part1("src/day10/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Total rating of trailheads: {} (Duration: {:.2?})",
-        part2("src/day10/input.txt"),
-        start.elapsed()
-    )
+    let mut runner = Runner::new(10);
+    let input = runner.input().to_string();
+    runner.parse(|| TopographicMap::from_file(&input));
+    runner.part("Total score of trailheads", || part1(&input));
+    runner.part("Total rating of trailheads", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
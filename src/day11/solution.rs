@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::Read;
-use std::time::Instant;
 use std::collections::HashMap;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Default)]
 struct Stones(Vec<u64>);
@@ -80,19 +80,12 @@ fn part2(file_path: &str) -> usize {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Total stones after 25 blinks: {} (Duration: {:.2?})",
-        part1("src/day11/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Total stones after 75 blinks: {} (Duration: {:.2?})",
-        part2("src/day11/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(11);
+    let input = runner.input().to_string();
+    runner.parse(|| Stones::from_file(&input));
+    runner.part("Total stones after 25 blinks", || part1(&input));
+    runner.part("Total stones after 75 blinks", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
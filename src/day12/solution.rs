@@ -1,7 +1,7 @@
 use std::io::Read;
 use std::fs::File;
 use std::collections::HashSet;
-use std::time::Instant;
+use advent_of_code_2024::runner::Runner;
 
 static DIRS: [(i32, i32); 4] = [(0,-1), (1, 0), (0, 1), (-1,0)];
 
@@ -292,19 +292,12 @@ fn part2(file_path: &str) -> i32 {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Total price of fence: {} (Duration: {:.2?})",
-        part1("src/day12/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Total price of fence w/ bulk discount: {} (Duration: {:.2?})",
-        part2("src/day12/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(12);
+    let input = runner.input().to_string();
+    runner.parse(|| Farm::from_file(&input));
+    runner.part("Total price of fence", || part1(&input));
+    runner.part("Total price of fence w/ bulk discount", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
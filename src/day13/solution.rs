@@ -1,7 +1,8 @@
 use std::io::Read;
 use std::fs::File;
 use std::mem::swap;
-use std::time::Instant;
+use advent_of_code_2024::cache::{cached, Cacheable, Decoder};
+use advent_of_code_2024::runner::Runner;
 
 fn parse_input(file_path: &str) -> Vec<ClawMachine> {
     let mut buf = String::new();
@@ -43,6 +44,17 @@ struct ClawMachine {
     prize: (i64, i64),
 }
 
+impl Cacheable for ClawMachine {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (self.btn_a, self.btn_b, self.prize).encode(buf);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        let (btn_a, btn_b, prize) = Cacheable::decode(r)?;
+        Some(Self { btn_a, btn_b, prize })
+    }
+}
+
 impl ClawMachine {
     fn calculate_press_counts(&self) -> Option<(i64, i64)> {
         let a_num = self.btn_b.1*self.prize.0 - self.btn_b.0*self.prize.1;
@@ -71,7 +83,7 @@ impl ClawMachine {
 
 fn part1(file_path: &str) -> i64 {
     let mut total_cost = 0;
-    let machines = parse_input(file_path);
+    let machines = cached(file_path, parse_input);
 
     for machine in machines.iter() {
         if let Some((a_presses, b_presses)) = machine.calculate_press_counts() {
@@ -83,7 +95,7 @@ fn part1(file_path: &str) -> i64 {
 
 fn part2(file_path: &str) -> i64 {
     let mut total_cost = 0;
-    let mut machines = parse_input(file_path);
+    let mut machines = cached(file_path, parse_input);
 
     for machine in machines.iter_mut() {
         machine.prize.0 += 10000000000000; 
@@ -96,19 +108,12 @@ fn part2(file_path: &str) -> i64 {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Fewest tokens to win: {} (Duration: {:.2?})",
-        part1("src/day13/input.txt"),
-        start.elapsed()
-    );
-    
-    start = Instant::now();
-    println!(
-        "Fewest tokens to win after position shift: {} (Duration: {:.2?})",
-        part2("src/day13/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(13);
+    let input = runner.input().to_string();
+    runner.parse(|| parse_input(&input));
+    runner.part("Fewest tokens to win", || part1(&input));
+    runner.part("Fewest tokens to win after position shift", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -120,6 +125,15 @@ mod tests {
         assert_eq!(480, part1("src/day13/test.txt"));
     }
 
+    #[test]
+    fn test_cache_roundtrip() {
+        let machines = parse_input("src/day13/test.txt");
+        let mut buf = Vec::new();
+        machines.encode(&mut buf);
+        let decoded = Vec::<ClawMachine>::decode(&mut Decoder::new(&buf)).unwrap();
+        assert_eq!(format!("{:?}", machines), format!("{:?}", decoded));
+    }
+
     #[test]
     fn test_part2() {
         
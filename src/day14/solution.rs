@@ -1,7 +1,8 @@
 use std::io::Read;
 use std::fs::File;
-use std::time::Instant;
 use std::collections::HashSet;
+use advent_of_code_2024::{debug, info};
+use advent_of_code_2024::runner::Runner;
 
 
 #[derive(Debug)]
@@ -72,19 +73,21 @@ impl Bathroom {
             let b = rob.pos.1 * 2 / self.height;
             counts[a + 2*b] += 1;
         }
-        println!("{:?}", counts);
+        debug!("Quadrant counts: {:?}", counts);
         return counts[0] * counts[1] * counts[2] * counts[3];
     }
 
-    fn render_robots(&self) {
+    fn render_robots(&self) -> String {
         let mut canvas = vec![vec![' '; self.width]; self.height];
         for robot in self.robots.iter() {
             canvas[robot.pos.1][robot.pos.0] = '#';
         }
+        let mut s = String::new();
         for v in canvas.iter() {
-            let line: String = v.iter().collect();
-            println!("{}", line);
+            s.extend(v.iter());
+            s.push('\n');
         }
+        s
     }
 
     fn contains_picture(&self) -> bool {
@@ -108,11 +111,16 @@ fn part1(file_path: &str, height: usize, width: usize) -> i32 {
 
 fn part2(file_path: &str, height: usize, width: usize) -> i32 {
     let mut bathroom = Bathroom::from_file(file_path, height, width);
+    let seconds = find_easter_egg(&mut bathroom);
+    info!("Easter egg after {} seconds:\n{}", seconds, bathroom.render_robots());
+    seconds
+}
+
+fn find_easter_egg(bathroom: &mut Bathroom) -> i32 {
     let mut seconds = 1;
     loop {
         bathroom.step_n_times(1);
         if bathroom.contains_picture() {
-            bathroom.render_robots();
             break;
         }
         seconds += 1;
@@ -121,27 +129,30 @@ fn part2(file_path: &str, height: usize, width: usize) -> i32 {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Safety factor after 100 steps: {} (Duration: {:.2?})",
-        part1("src/day14/input.txt", 103, 101),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Seconds until easter egg: {} (Duration: {:.2?})",
-        part2("src/day14/input.txt", 103, 101),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(14);
+    let input = runner.input().to_string();
+    let height = runner.param("height", 103);
+    let width = runner.param("width", 101);
+    runner.parse(|| Bathroom::from_file(&input, height, width));
+    runner.part("Safety factor after 100 steps", || part1(&input, height, width));
+    runner.part("Seconds until easter egg", || part2(&input, height, width));
+    runner.finish();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code_2024::snapshot::assert_snapshot;
 
     #[test]
     fn test_part1() {
         assert_eq!(12, part1("src/day14/test.txt", 7, 11));
     }
+
+    #[test]
+    fn test_easter_egg_snapshot() {
+        let mut bathroom = Bathroom::from_file("src/day14/input.txt", 103, 101);
+        assert_eq!(8149, find_easter_egg(&mut bathroom));
+        assert_snapshot("src/day14/snapshots/easter_egg.txt", &bathroom.render_robots());
+    }
 }
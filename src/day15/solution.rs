@@ -1,7 +1,8 @@
 use std::fs::File;
 use std::io::Read;
 use std::collections::HashSet;
-use std::time::Instant;
+use advent_of_code_2024::debug;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug, Default)]
 struct Warehouse {
@@ -104,32 +105,58 @@ impl Warehouse {
         }
     }
 
+    fn render(&self) -> String {
+        let mut s = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.walls.contains(&(x, y)) {
+                    s.push('#');
+                } else if self.boxes.contains(&(x, y)) {
+                    s.push('O');
+                } else if self.robot == (x, y) {
+                    s.push('@');
+                } else {
+                    s.push('.');
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
 }
 
 fn part1(file_path: &str) -> usize {
     let mut warehouse = Warehouse::from_file(file_path);
     warehouse.make_moves();
+    debug!("Final warehouse state:\n{}", warehouse.render());
     warehouse.calulate_coordinate_sum()
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Sum of GPS coordinates: {} (Duration: {:.2?})",
-        part1("src/day15/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(15);
+    let input = runner.input().to_string();
+    runner.parse(|| Warehouse::from_file(&input));
+    runner.part("Sum of GPS coordinates", || part1(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code_2024::snapshot::assert_snapshot;
 
     #[test]
     fn test_part1() {
         assert_eq!(10092, part1("src/day15/test.txt"));
     }
 
+    #[test]
+    fn test_final_state_snapshot() {
+        let mut warehouse = Warehouse::from_file("src/day15/test.txt");
+        warehouse.make_moves();
+        assert_snapshot("src/day15/snapshots/final_state.txt", &warehouse.render());
+    }
+
     #[test]
     fn test_part2() {
         
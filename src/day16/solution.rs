@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::Read;
 use std::collections::HashSet;
-use std::time::Instant;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug, Default)]
 struct Maze {
@@ -142,19 +142,12 @@ fn part2(file_path: &str) -> usize {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Lowest possible score: {} (Duration: {:.2?})",
-        part1("src/day16/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Lowest possible score: {} (Duration: {:.2?})",
-        part2("src/day16/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(16);
+    let input = runner.input().to_string();
+    runner.parse(|| Maze::from_file(&input));
+    runner.part("Lowest possible score", || part1(&input));
+    runner.part("Lowest possible score", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -1,6 +1,8 @@
 use std::io::Read;
 use std::fs::File;
-use std::time::Instant;
+use advent_of_code_2024::debug;
+use advent_of_code_2024::cache::{cached, Cacheable, Decoder};
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug)]
 struct Computer {
@@ -11,6 +13,21 @@ struct Computer {
     c_reg: usize,
 }
 
+impl Cacheable for Computer {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.ip.encode(buf);
+        self.program.encode(buf);
+        (self.a_reg, self.b_reg, self.c_reg).encode(buf);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        let ip = Cacheable::decode(r)?;
+        let program = Cacheable::decode(r)?;
+        let (a_reg, b_reg, c_reg) = Cacheable::decode(r)?;
+        Some(Self { ip, program, a_reg, b_reg, c_reg })
+    }
+}
+
 impl Computer {
     fn from_file(file_path: &str) -> Self {
         let mut buf = String::new();
@@ -102,7 +119,7 @@ impl Computer {
         for _ in 0..100 {
             self.a_reg = a_reg;
             let op = self.execute_program();
-            println!("{:?}", op);
+            debug!("{:?}", op);
             a_reg += 1;
         }
         return 0;
@@ -146,32 +163,24 @@ impl Computer {
 }
 
 fn part1(file_path: &str) -> Vec<usize> {
-    let mut c = Computer::from_file(file_path);
+    let mut c = cached(file_path, Computer::from_file);
     c.execute_program()
 }
 
 fn part2(file_path: &str) -> usize {
-    let mut c = Computer::from_file(file_path);
+    let mut c = cached(file_path, Computer::from_file);
     c.get_fixed_point()
 }
 
 fn main() {
-    let mut start = Instant::now();
-
-    let output = part1("src/day17/input.txt").iter()
-        .map(|e| e.to_string()).collect::<Vec<String>>().join(",");
-    println!(
-        "Output: {} (Duration: {:.2?})",
-        output,
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Fix-point of A register: {} (Duration: {:.2?})",
-        part2("src/day17/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(17);
+    let input = runner.input().to_string();
+    runner.parse(|| Computer::from_file(&input));
+    runner.part("Output", || {
+        part1(&input).iter().map(|e| e.to_string()).collect::<Vec<String>>().join(",")
+    });
+    runner.part("Fix-point of A register", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
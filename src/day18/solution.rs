@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io::Read;
-use std::time::Instant;
+use advent_of_code_2024::debug;
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug)]
 struct MemoryMaze {
@@ -114,7 +115,7 @@ impl MemoryMaze{
         return false;
     }
 
-    fn display(&self) {
+    fn render(&self) -> String {
         let mut s = String::new();
 
         for y in 0..=self.h_range {
@@ -127,13 +128,14 @@ impl MemoryMaze{
             }
             s.push('\n');
         }
-        println!("{}", s);
+        s
     }
 }
 
 fn part1(file_path: &str, h_range: usize, v_range: usize, fallen: usize) -> usize {
     let mut mm = MemoryMaze::from_file(file_path, h_range, v_range);
     mm.fallen = fallen;
+    debug!("Corrupted memory:\n{}", mm.render());
     mm.get_shortest_path().expect("MemoryMaze contains no valid path to goal")
 }
 
@@ -143,7 +145,7 @@ fn part2(file_path: &str, h_range: usize, v_range: usize) -> (usize, usize) {
     let mut hi = mm.points.len() - 1;
 
     while lo < hi {
-        println!("{}, {}", lo, hi);
+        debug!("{}, {}", lo, hi);
         mm.fallen = (lo + hi) / 2;
         if mm.has_path() {
             lo = mm.fallen + 1;
@@ -158,24 +160,20 @@ fn part2(file_path: &str, h_range: usize, v_range: usize) -> (usize, usize) {
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Lowest possible score: {} (Duration: {:.2?})",
-        part1("src/day18/input.txt", 70, 70, 1024),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Path blocking byte: {:?} (Duration: {:.2?})",
-        part2("src/day18/input.txt", 70, 70),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(18);
+    let input = runner.input().to_string();
+    let size = runner.param("size", 70);
+    let fallen = runner.param("fallen", 1024);
+    runner.parse(|| MemoryMaze::from_file(&input, size, size));
+    runner.part("Lowest possible score", || part1(&input, size, size, fallen));
+    runner.part("Path blocking byte", || format!("{:?}", part2(&input, size, size)));
+    runner.finish();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code_2024::snapshot::assert_snapshot;
 
     #[test]
     fn test_part1() {
@@ -186,4 +184,11 @@ mod tests {
     fn test_part2() {
         assert_eq!((6, 1), part2("src/day18/test.txt", 6, 6));
     }
+
+    #[test]
+    fn test_corrupted_grid_snapshot() {
+        let mut mm = MemoryMaze::from_file("src/day18/test.txt", 6, 6);
+        mm.fallen = 12;
+        assert_snapshot("src/day18/snapshots/corrupted_12.txt", &mm.render());
+    }
 }
@@ -1,7 +1,9 @@
 use std::io::Read;
 use std::fs::File;
-use std::time::Instant;
 use std::collections::HashMap;
+use advent_of_code_2024::trace;
+use advent_of_code_2024::cache::{cached, Cacheable, Decoder};
+use advent_of_code_2024::runner::Runner;
 
 #[derive(Debug, Default)]
 struct TowelDesigner {
@@ -9,6 +11,17 @@ struct TowelDesigner {
     designs: Vec<String>,
 }
 
+impl Cacheable for TowelDesigner {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.towels.encode(buf);
+        self.designs.encode(buf);
+    }
+
+    fn decode(r: &mut Decoder) -> Option<Self> {
+        Some(Self { towels: Cacheable::decode(r)?, designs: Cacheable::decode(r)? })
+    }
+}
+
 impl TowelDesigner {
     fn from_file(file_path: &str) -> Self {
         let mut buf = String::new();
@@ -56,7 +69,7 @@ impl TowelDesigner {
             return true;
         }
 
-        println!("{}", design);
+        trace!("{}", design);
 
         for towel in self.towels.iter() {
             if design.len() < towel.len() { continue; }
@@ -103,7 +116,7 @@ impl TowelDesigner {
             return 1;
         }
 
-        println!("{}", design);
+        trace!("{}", design);
 
         for towel in self.towels.iter() {
             if design.len() < towel.len() { continue; }
@@ -129,29 +142,22 @@ impl TowelDesigner {
 }
 
 fn part1(file_path: &str) -> usize {
-    let td = TowelDesigner::from_file(file_path);
+    let td = cached(file_path, TowelDesigner::from_file);
     td.count_possible_designs()
 }
 
 fn part2(file_path: &str) -> usize {
-    let td = TowelDesigner::from_file(file_path);
+    let td = cached(file_path, TowelDesigner::from_file);
     td.count_all_combinations()
 }
 
 fn main() {
-    let mut start = Instant::now();
-    println!(
-        "Possible towel designs: {} (Duration: {:.2?})",
-        part1("src/day19/input.txt"),
-        start.elapsed()
-    );
-
-    start = Instant::now();
-    println!(
-        "Possible towel design combos: {} (Duration: {:.2?})",
-        part2("src/day19/input.txt"),
-        start.elapsed()
-    );
+    let mut runner = Runner::new(19);
+    let input = runner.input().to_string();
+    runner.parse(|| TowelDesigner::from_file(&input));
+    runner.part("Possible towel designs", || part1(&input));
+    runner.part("Possible towel design combos", || part2(&input));
+    runner.finish();
 }
 
 #[cfg(test)]
@@ -0,0 +1,301 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::{error, fmt};
+
+const BASE_URL: &str = "https://adventofcode.com/2024";
+const LAYOUT: &str = "day{day}/input.txt";
+const USER_AGENT: &str = "github.com/endrebjorgo/advent-of-code-2024";
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    MissingSession(usize),
+    InvalidUrl(String),
+    InvalidResponse(String),
+    Http { status: u16, url: String },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Io(e) => write!(f, "{}", e),
+            InputError::MissingSession(day) => write!(
+                f, "no input cached for day {} and no session token set", day
+            ),
+            InputError::InvalidUrl(url) => write!(f, "unsupported url: {}", url),
+            InputError::InvalidResponse(msg) => write!(f, "invalid response: {}", msg),
+            InputError::Http { status, url } => write!(f, "GET {} returned {}", url, status),
+        }
+    }
+}
+
+impl error::Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        InputError::Io(e)
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+pub trait HttpClient {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, InputError>;
+}
+
+/// Shells out to `curl`, which takes care of TLS for the real AoC server.
+/// Headers are passed on stdin so the session token doesn't show up in `ps`.
+#[derive(Debug, Default)]
+pub struct CurlClient;
+
+impl HttpClient for CurlClient {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, InputError> {
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--header", "@-"])
+            .args(["--write-out", "\n%{http_code}", url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        {
+            let mut stdin = child.stdin.take().unwrap();
+            for (name, value) in headers.iter() {
+                writeln!(stdin, "{}: {}", name, value)?;
+            }
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let msg = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(InputError::InvalidResponse(msg));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (body, status) = stdout.rsplit_once('\n')
+            .ok_or_else(|| InputError::InvalidResponse(stdout.to_string()))?;
+        let status = status.trim().parse()
+            .map_err(|_| InputError::InvalidResponse(status.to_string()))?;
+
+        Ok(Response { status, body: body.to_string() })
+    }
+}
+
+/// Minimal plain-HTTP client on top of `TcpStream`. It can't talk to the AoC
+/// server (no TLS), but is enough for a local stub server.
+#[derive(Debug, Default)]
+pub struct TcpClient;
+
+impl HttpClient for TcpClient {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, InputError> {
+        let rest = url.strip_prefix("http://")
+            .ok_or_else(|| InputError::InvalidUrl(url.to_string()))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, addr) = match authority.split_once(':') {
+            Some((host, _)) => (host, authority.to_string()),
+            None => (authority, format!("{}:80", authority)),
+        };
+
+        let mut request = format!("GET {} HTTP/1.1\r\nHost: {}\r\n", path, host);
+        for (name, value) in headers.iter() {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("Connection: close\r\n\r\n");
+
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(request.as_bytes())?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        let raw = String::from_utf8_lossy(&raw);
+
+        let (head, body) = raw.split_once("\r\n\r\n")
+            .ok_or_else(|| InputError::InvalidResponse(raw.to_string()))?;
+        let status = head.split_whitespace().nth(1)
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| InputError::InvalidResponse(head.to_string()))?;
+
+        Ok(Response { status, body: body.to_string() })
+    }
+}
+
+/// Looks up puzzle inputs in the cache directory, downloading and storing them
+/// there the first time a day is requested. Files are placed according to the
+/// layout, where `{day}` is replaced by the zero-padded day number.
+#[derive(Debug)]
+pub struct InputManager<C: HttpClient = CurlClient> {
+    cache_dir: PathBuf,
+    layout: String,
+    base_url: String,
+    session: Option<String>,
+    client: C,
+}
+
+impl InputManager {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self::with_client(cache_dir, CurlClient)
+    }
+}
+
+impl<C: HttpClient> InputManager<C> {
+    pub fn with_client(cache_dir: impl Into<PathBuf>, client: C) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            layout: LAYOUT.to_string(),
+            base_url: BASE_URL.to_string(),
+            session: None,
+            client,
+        }
+    }
+
+    pub fn layout(mut self, layout: &str) -> Self {
+        self.layout = layout.to_string();
+        self
+    }
+
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn session(mut self, token: impl Into<String>) -> Self {
+        self.session = Some(token.into());
+        self
+    }
+
+    pub fn path(&self, day: usize) -> PathBuf {
+        self.cache_dir.join(self.layout.replace("{day}", &format!("{:02}", day)))
+    }
+
+    pub fn resolve(&self, day: usize) -> Result<PathBuf, InputError> {
+        let path = self.path(day);
+        if path.exists() {
+            return Ok(path);
+        }
+
+        let body = self.fetch(day)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, body)?;
+        Ok(path)
+    }
+
+    pub fn fetch(&self, day: usize) -> Result<String, InputError> {
+        let session = self.session.as_deref()
+            .ok_or(InputError::MissingSession(day))?;
+        let url = format!("{}/day/{}/input", self.base_url, day);
+        let cookie = format!("session={}", session);
+
+        let response = self.client.get(
+            &url,
+            &[("Cookie", &cookie), ("User-Agent", USER_AGENT)]
+        )?;
+
+        // Don't cache error pages as puzzle input.
+        if response.status != 200 {
+            return Err(InputError::Http { status: response.status, url });
+        }
+        Ok(response.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::thread;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir()
+            .join(format!("aoc-input-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // Serves a single request and hands back what the client sent.
+    fn stub_server(status: &str, body: &str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}", status, body.len(), body
+        );
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 512];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 { break; }
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_fetches_and_caches_missing_input() {
+        let dir = temp_dir("fetch");
+        let (url, server) = stub_server("200 OK", "1 2\n3 4\n");
+        let manager = InputManager::with_client(&dir, TcpClient)
+            .base_url(&url)
+            .session("abc123");
+
+        let path = manager.resolve(1).unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(path, dir.join("day01").join("input.txt"));
+        assert_eq!("1 2\n3 4\n", fs::read_to_string(&path).unwrap());
+        assert!(request.starts_with("GET /day/1/input HTTP/1.1\r\n"));
+        assert!(request.contains("Cookie: session=abc123\r\n"));
+
+        // Second lookup is served from the cache; nothing is listening anymore.
+        assert_eq!(path, manager.resolve(1).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_error_status_is_not_cached() {
+        let dir = temp_dir("status");
+        let (url, server) = stub_server("400 Bad Request", "Puzzle inputs differ by user.");
+        let manager = InputManager::with_client(&dir, TcpClient)
+            .base_url(&url)
+            .session("expired");
+
+        match manager.resolve(2) {
+            Err(InputError::Http { status, .. }) => assert_eq!(400, status),
+            other => panic!("expected http error, got {:?}", other),
+        }
+        server.join().unwrap();
+        assert!(!Path::new(&dir.join("day02").join("input.txt")).exists());
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        let manager = InputManager::new("inputs").layout("{day}.txt");
+        assert_eq!(PathBuf::from("inputs/07.txt"), manager.path(7));
+    }
+
+    #[test]
+    fn test_missing_session() {
+        let dir = temp_dir("session");
+        let manager = InputManager::with_client(&dir, TcpClient);
+
+        assert!(matches!(manager.resolve(3), Err(InputError::MissingSession(3))));
+    }
+}
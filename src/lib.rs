@@ -0,0 +1,14 @@
+// Shared helpers used by the daily solutions in src/dayNN/solution.rs.
+
+pub mod alloc;
+pub mod cache;
+pub mod config;
+pub mod input;
+pub mod log;
+pub mod runner;
+pub mod search;
+pub mod snapshot;
+
+#[cfg(feature = "alloc-profile")]
+#[global_allocator]
+static GLOBAL: alloc::CountingAllocator = alloc::CountingAllocator;
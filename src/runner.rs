@@ -0,0 +1,179 @@
+use std::env;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::alloc::{self, AllocStats};
+use crate::cache;
+use crate::config::{Config, OutputFormat};
+use crate::log;
+
+#[derive(Debug)]
+struct PartResult {
+    label: String,
+    answer: Option<String>,
+    duration: Duration,
+    alloc: Option<AllocStats>,
+}
+
+/// Entry point shared by the solution binaries. Loads the config, resolves the
+/// day's input and reports the answers in the configured output format.
+///
+/// Passing `--bench` also times parsing on its own and runs everything
+/// `bench.iterations` times, reporting the mean duration. When built with
+/// `--features alloc-profile` it reports the allocations of a single run too.
+#[derive(Debug)]
+pub struct Runner {
+    day: usize,
+    config: Config,
+    input: String,
+    args: Vec<String>,
+    results: Vec<PartResult>,
+    sections: Vec<(String, String)>,
+}
+
+impl Runner {
+    pub fn new(day: usize) -> Self {
+        let config = Config::load()
+            .unwrap_or_else(|e| panic!("Invalid configuration: {}", e));
+        log::set_level(config.log_level);
+        if config.cache.enabled {
+            cache::enable(&config.cache.dir);
+        }
+
+        let input = match config.input_manager().resolve(day) {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(e) => panic!("Could not get input for day {}: {}", day, e),
+        };
+        let args = env::args().skip(1).collect();
+
+        Self { day, config, input, args, results: Vec::new(), sections: Vec::new() }
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn param<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.config.param(self.day, key, default)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.args.iter().any(|a| a == name)
+    }
+
+    pub fn is_bench(&self) -> bool {
+        self.flag("--bench")
+    }
+
+    /// Benchmarks the day's parser on its own. Does nothing outside `--bench`,
+    /// since the parts parse the input themselves.
+    pub fn parse<T>(&mut self, f: impl FnMut() -> T) {
+        if !self.is_bench() { return; }
+        let (_, result) = self.run("Parse", f);
+        self.report(&result);
+        self.results.push(result);
+    }
+
+    pub fn part<T: Display>(&mut self, label: &str, f: impl FnMut() -> T) {
+        let (answer, mut result) = self.run(label, f);
+        result.answer = Some(answer.to_string());
+        self.report(&result);
+        self.results.push(result);
+    }
+
+    fn run<T>(&self, label: &str, mut f: impl FnMut() -> T) -> (T, PartResult) {
+        let bench = self.is_bench();
+        let iterations = if bench { self.config.bench.iterations.max(1) } else { 1 };
+
+        let start = Instant::now();
+        let (mut value, stats) = alloc::measure(&mut f);
+        for _ in 1..iterations {
+            value = f();
+        }
+        let duration = start.elapsed() / iterations as u32;
+
+        let alloc = if bench && alloc::ENABLED { Some(stats) } else { None };
+        (value, PartResult { label: label.to_string(), answer: None, duration, alloc })
+    }
+
+    fn report(&self, result: &PartResult) {
+        if self.config.output != OutputFormat::Text { return; }
+
+        match &result.answer {
+            Some(answer) => println!(
+                "{}: {} (Duration: {:.2?})", result.label, answer, result.duration
+            ),
+            None => println!("{} (Duration: {:.2?})", result.label, result.duration),
+        }
+        if let Some(stats) = result.alloc {
+            println!("    {}", stats);
+        }
+    }
+
+    /// Extra output such as a report, printed as `text` right away or added
+    /// to the JSON output under `name`. `json` has to be a JSON value.
+    pub fn section(&mut self, name: &str, text: &str, json: String) {
+        match self.config.output {
+            OutputFormat::Text => print!("{}", text),
+            OutputFormat::Json => self.sections.push((name.to_string(), json)),
+        }
+    }
+
+    pub fn finish(self) {
+        if self.config.output != OutputFormat::Json { return; }
+
+        let parts: Vec<String> = self.results.iter()
+            .map(|r| {
+                let mut s = format!("{{\"label\":{}", json_string(&r.label));
+                if let Some(answer) = &r.answer {
+                    s.push_str(&format!(",\"answer\":{}", json_string(answer)));
+                }
+                s.push_str(&format!(",\"duration_ns\":{}", r.duration.as_nanos()));
+                if let Some(stats) = r.alloc {
+                    s.push_str(&format!(
+                        ",\"allocations\":{},\"allocated_bytes\":{},\"peak_bytes\":{}",
+                        stats.allocations, stats.allocated, stats.peak
+                    ));
+                }
+                s.push('}');
+                s
+            })
+            .collect();
+        let sections: String = self.sections.iter()
+            .map(|(name, json)| format!(",{}:{}", json_string(name), json))
+            .collect();
+        println!("{{\"day\":{},\"parts\":[{}]{}}}", self.day, parts.join(","), sections);
+    }
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(r#""Output: \"4,6\"\n""#, json_string("Output: \"4,6\"\n"));
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Index of the pattern in the list the matcher was built from.
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Match {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Finds every occurrence of a set of patterns in a single pass over the
+/// input, using the Aho-Corasick automaton. Transitions are precomputed for
+/// every byte, so scanning is one table lookup per input byte.
+///
+/// Empty patterns never match.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    lens: Vec<usize>,
+    transitions: Vec<[u32; 256]>,
+    // Patterns ending in each state, longest first.
+    outputs: Vec<Vec<usize>>,
+}
+
+impl AhoCorasick {
+    pub fn new<P: AsRef<[u8]>>(patterns: &[P]) -> Self {
+        let mut transitions: Vec<[u32; 256]> = vec![[0; 256]];
+        let mut outputs: Vec<Vec<usize>> = vec![Vec::new()];
+        // A transition to the root is never a trie edge, so 0 means "none".
+        for (i, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_ref();
+            if pattern.is_empty() { continue; }
+
+            let mut state = 0;
+            for &b in pattern.iter() {
+                if transitions[state][b as usize] == 0 {
+                    transitions.push([0; 256]);
+                    outputs.push(Vec::new());
+                    transitions[state][b as usize] = (transitions.len() - 1) as u32;
+                }
+                state = transitions[state][b as usize] as usize;
+            }
+            outputs[state].push(i);
+        }
+
+        // Breadth-first, so the failure state of every state is done before
+        // the state itself. Missing edges are filled in with the transition
+        // of the failure state, turning the trie into a DFA.
+        let mut fail = vec![0usize; transitions.len()];
+        let mut queue: VecDeque<usize> = transitions[0].iter()
+            .filter(|&&next| next != 0)
+            .map(|&next| next as usize)
+            .collect();
+
+        while let Some(state) = queue.pop_front() {
+            let inherited = outputs[fail[state]].clone();
+            outputs[state].extend(inherited);
+
+            let fallback = transitions[fail[state]];
+            for (b, next) in transitions[state].iter_mut().enumerate() {
+                if *next == 0 {
+                    *next = fallback[b];
+                } else {
+                    fail[*next as usize] = fallback[b] as usize;
+                    queue.push_back(*next as usize);
+                }
+            }
+        }
+
+        let lens = patterns.iter().map(|p| p.as_ref().len()).collect();
+        Self { lens, transitions, outputs }
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// All matches, including overlapping ones, ordered by where they end.
+    /// Matches ending at the same position come longest first.
+    pub fn find_iter<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        haystack.iter().enumerate()
+            .scan(0usize, move |state, (i, &b)| {
+                *state = self.transitions[*state][b as usize] as usize;
+                Some((i + 1, *state))
+            })
+            .flat_map(move |(end, state)| {
+                self.outputs[state].iter().map(move |&pattern| Match {
+                    pattern,
+                    start: end - self.lens[pattern],
+                    end,
+                })
+            })
+    }
+
+    /// The longest pattern starting at each position of the haystack.
+    pub fn longest_at(&self, haystack: &[u8]) -> Vec<Option<usize>> {
+        let mut longest: Vec<Option<usize>> = vec![None; haystack.len()];
+        for m in self.find_iter(haystack) {
+            let slot = &mut longest[m.start];
+            if !slot.is_some_and(|p| self.lens[p] >= m.len()) {
+                *slot = Some(m.pattern);
+            }
+        }
+        longest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(patterns: &[&str], haystack: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        for end in 1..=haystack.len() {
+            let mut here: Vec<(usize, usize)> = patterns.iter().enumerate()
+                .filter(|(_, p)| !p.is_empty() && haystack[..end].ends_with(*p))
+                .map(|(i, p)| (i, end - p.len()))
+                .collect();
+            here.sort_by_key(|&(_, start)| start);
+            matches.extend(here);
+        }
+        matches
+    }
+
+    #[test]
+    fn test_find_iter() {
+        let patterns = ["he", "she", "his", "hers", ""];
+        let haystack = "ushers and his sheep";
+        let matcher = AhoCorasick::new(&patterns);
+        let found: Vec<(usize, usize)> = matcher.find_iter(haystack.as_bytes())
+            .map(|m| (m.pattern, m.start))
+            .collect();
+
+        assert_eq!(5, matcher.pattern_count());
+        assert_eq!(naive(&patterns, haystack), found);
+        assert_eq!(vec![(1, 1), (0, 2), (3, 2), (2, 11), (1, 15), (0, 16)], found);
+    }
+
+    #[test]
+    fn test_longest_at() {
+        let matcher = AhoCorasick::new(&["do", "don't", "mul"]);
+        let longest = matcher.longest_at(b"don't()do()mul");
+        assert_eq!(Some(1), longest[0]);
+        assert_eq!(Some(0), longest[7]);
+        assert_eq!(Some(2), longest[11]);
+        assert_eq!(3, longest.iter().flatten().count());
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Compares a rendering against the snapshot stored at `path`, panicking with
+/// a line diff if they differ. Missing snapshots are written, and running the
+/// tests with `UPDATE_SNAPSHOTS=1` overwrites existing ones.
+pub fn assert_snapshot(path: &str, actual: &str) {
+    let path = Path::new(path);
+    let update = env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1");
+
+    if update || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(path).unwrap();
+    if expected != actual {
+        panic!(
+            "Snapshot {} does not match (-expected +actual):\n{}\
+             Rerun with UPDATE_SNAPSHOTS=1 to accept the new output.",
+            path.display(),
+            diff(&expected, actual)
+        );
+    }
+}
+
+/// Line diff based on the longest common subsequence of the two inputs.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    // lcs[i][j] is the LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i+1][j+1] + 1
+            } else {
+                lcs[i+1][j].max(lcs[i][j+1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push_str(&format!(" {}\n", a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i+1][j] >= lcs[i][j+1]) {
+            out.push_str(&format!("-{}\n", a[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!("+{}\n", b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            " #..\n-.#.\n+.##\n ..#\n",
+            diff("#..\n.#.\n..#\n", "#..\n.##\n..#\n")
+        );
+    }
+}